@@ -7,16 +7,26 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    blob_storage::{BlobStorage, BlobStorageDryRun},
-    Client,
+    blob_storage::{BlobStorage, BlobStorageDryRun, BlobStorageUpload},
+    ChunkCache, Client,
 };
 use crate::Error;
 use bincode::{deserialize, serialize};
-use log::{info, trace};
+use futures::future;
+use log::{debug, info, trace};
 use self_encryption::{DataMap, SelfEncryptor};
 use serde::{Deserialize, Serialize};
-use sn_data_types::{Blob, BlobAddress, PrivateBlob, PublicBlob, PublicKey};
+use sn_data_types::{Blob, BlobAddress, PrivateBlob, PublicBlob, PublicKey, Token};
 use sn_messaging::client::{BlobRead, BlobWrite, DataCmd, DataQuery, Query, QueryResponse};
+use std::{iter, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::mpsc::Receiver,
+};
+
+// Number of bytes read from a source at a time when storing a Blob from a reader.
+// This matches the maximum size of a chunk.
+const READER_BUFFER_SIZE: usize = 1024 * 1024;
 
 #[derive(Serialize, Deserialize)]
 enum DataMapLevel {
@@ -110,6 +120,8 @@ impl Client {
     /// # let balance_after_write = client.get_local_balance().await; assert_ne!(initial_balance, balance_after_write); Ok(()) } ); }
     /// ```
     pub async fn store_public_blob(&self, data: &[u8]) -> Result<BlobAddress, Error> {
+        self.check_writable()?;
+
         self.create_new_blob(data, true).await
     }
 
@@ -141,21 +153,228 @@ impl Client {
     /// # let balance_after_write = client.get_local_balance().await; assert_ne!(initial_balance, balance_after_write); Ok(()) } ); }
     /// ```
     pub async fn store_private_blob(&self, data: &[u8]) -> Result<BlobAddress, Error> {
+        self.check_writable()?;
+
         self.create_new_blob(data, false).await
     }
 
+    /// Set the number of chunks held waiting to be stored when storing Blobs, and fetched
+    /// concurrently ahead of reading them when reading Blobs. It defaults to 10.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// client.set_chunk_concurrency(4);
+    /// let _address = client.store_public_blob(b"some data").await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn set_chunk_concurrency(&mut self, limit: usize) {
+        self.chunk_concurrency = limit.max(1);
+    }
+
+    /// Set a cache which chunks are looked up in before fetching them from the network when
+    /// reading Blobs, and which the chunks fetched are added to.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::{client::ChunkCache, Client};
+    /// use sn_data_types::BlobAddress;
+    /// use std::path::Path;
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// let target_blob = BlobAddress::Public(XorName::random());
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// // Hold up to 1GB of chunks in the cache
+    /// let cache = ChunkCache::open(Path::new("/tmp/chunk_cache"), 1024 * 1024 * 1024)?;
+    /// client.set_chunk_cache(cache);
+    ///
+    /// let _data = client.read_blob(target_blob, None, None).await?;
+    /// // Reading it again doesn't go to the network
+    /// let _data = client.read_blob(target_blob, None, None).await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn set_chunk_cache(&mut self, cache: ChunkCache) {
+        self.chunk_cache = Some(Arc::new(cache));
+    }
+
+    /// Store the data read from the given source in a Blob on the network, either public or private.
+    ///
+    /// The data is read from the source and fed to self encryption a part at a time. The chunks
+    /// are stored as self encryption puts them, each paid for with a transfer of its own, and
+    /// no more than the chunk concurrency limit of the client are held waiting to be stored at
+    /// a time, see `set_chunk_concurrency`. Self encryption itself holds on to the data read
+    /// until the source is exhausted though, as it only splits it into chunks then, so memory
+    /// use still grows with the size of the data. It returns the address of the Blob once all
+    /// the chunks are stored.
+    ///
+    /// # Examples
+    ///
+    /// Store 100MB of data read from a source
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use tokio::io::{repeat, AsyncReadExt};
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let reader = repeat(7).take(100 * 1024 * 1024);
+    /// let _address = client.store_blob_from_reader(reader, true).await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn store_blob_from_reader<R>(
+        &self,
+        reader: R,
+        public: bool,
+    ) -> Result<BlobAddress, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.store_blob_from_reader_with_progress(reader, public, |_| ())
+            .await
+    }
+
+    /// Same as `store_blob_from_reader`, additionally calling `progress` every time a chunk
+    /// has been stored, with the number of bytes stored so far. Those are the bytes of the
+    /// encrypted chunks, the data map included, so they add up to slightly more than the size
+    /// of the data read. The total isn't known until the chunks are all produced, as they are
+    /// stored while being produced.
+    ///
+    /// # Examples
+    ///
+    /// Store 100MB of data, printing the progress of the upload
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use tokio::io::{repeat, AsyncReadExt};
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let reader = repeat(7).take(100 * 1024 * 1024);
+    /// let _address = client
+    ///     .store_blob_from_reader_with_progress(reader, true, |stored| {
+    ///         println!("{} bytes stored", stored)
+    ///     })
+    ///     .await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn store_blob_from_reader_with_progress<R, F>(
+        &self,
+        reader: R,
+        public: bool,
+        progress: F,
+    ) -> Result<BlobAddress, Error>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(u64),
+    {
+        self.check_writable()?;
+
+        self.self_encrypt_and_store(reader, public, progress).await
+    }
+
     async fn create_new_blob(&self, data: &[u8], public: bool) -> Result<BlobAddress, Error> {
-        let data_map = self.write_to_network(data, public).await?;
+        self.self_encrypt_and_store(data, public, |_| ()).await
+    }
 
-        let blob_content = serialize(&DataMapLevel::Root(data_map))?;
-        let blob = self.pack(blob_content, public).await?;
+    // Self encrypt the data read from the source, storing the chunks as self encryption puts
+    // them, then store the Blob holding its data map. The chunks are handed over to the
+    // uploader through a channel holding up to the chunk concurrency limit of the client, and
+    // each of them paid for with a transfer of its own. The data map is stored last, so the
+    // data can be read once it's found. `progress` is called with the number of bytes stored
+    // so far, every time a chunk is.
+    async fn self_encrypt_and_store<R, F>(
+        &self,
+        reader: R,
+        public: bool,
+        mut progress: F,
+    ) -> Result<BlobAddress, Error>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(u64),
+    {
+        let privately_owned = self.blob_owner(public);
+        self.get_history().await?;
+
+        let (blob_storage, chunks) =
+            BlobStorageUpload::new(privately_owned, self.chunk_concurrency);
+        let mut stored = 0;
+        let mut chunk_stored = |bytes: u64| {
+            stored += bytes;
+            progress(stored);
+        };
+        let (encrypted, uploaded) = future::join(
+            Self::self_encrypt_into(reader, blob_storage),
+            self.store_chunks(chunks, &mut chunk_stored),
+        )
+        .await;
+        // Self encryption fails if the upload stops, so the upload error is the one to surface
+        uploaded?;
+        let data_map = encrypted?;
+
+        let (_, blob, chunks) = Self::pack_data_map(data_map, privately_owned, Vec::new()).await?;
         let blob_address = *blob.address();
-
-        self.store_blob_on_network(blob).await?;
+        debug!(
+            "Storing Blob {:?}, and {} chunks of its data map",
+            blob_address,
+            chunks.len()
+        );
+        for chunk in chunks.into_iter().chain(iter::once(blob)) {
+            self.store_chunk(chunk, &mut chunk_stored).await?;
+        }
 
         Ok(blob_address)
     }
 
+    // Pay for and store the chunks received, one after the other, until the sender is done
+    // with them, or storing one of them fails
+    async fn store_chunks<F>(&self, mut chunks: Receiver<Blob>, mut stored: F) -> Result<(), Error>
+    where
+        F: FnMut(u64),
+    {
+        while let Some(chunk) = chunks.recv().await {
+            self.store_chunk(chunk, &mut stored).await?;
+        }
+
+        Ok(())
+    }
+
+    // Pay for and store a chunk, the transfer history being synced already, then call
+    // `stored` with its size
+    async fn store_chunk<F>(&self, chunk: Blob, stored: &mut F) -> Result<(), Error>
+    where
+        F: FnMut(u64),
+    {
+        let bytes = chunk.value().len() as u64;
+        self.pay_and_send_synced_data_command(DataCmd::Blob(BlobWrite::New(chunk)))
+            .await?;
+        stored(bytes);
+
+        Ok(())
+    }
+
+    // Owner of the Blobs stored by this client, if private
+    fn blob_owner(&self, public: bool) -> Option<PublicKey> {
+        if public {
+            None
+        } else {
+            Some(self.public_key())
+        }
+    }
+
     pub(crate) async fn fetch_blob_from_network(
         &self,
         address: BlobAddress,
@@ -223,6 +442,8 @@ impl Client {
     /// #  Ok(())} );}
     /// ```
     pub async fn delete_blob(&self, address: BlobAddress) -> Result<(), Error> {
+        self.check_writable()?;
+
         info!("Deleting blob at given address: {:?}", address);
 
         let mut data = self.fetch_blob_from_network(address).await?;
@@ -248,32 +469,83 @@ impl Client {
     /// Uses self_encryption to generate an encrypted Blob serialized data map,
     /// without connecting and/or writing to the network.
     pub async fn blob_data_map(
-        mut data: Vec<u8>,
+        data: Vec<u8>,
         privately_owned: Option<PublicKey>,
     ) -> Result<(DataMap, BlobAddress), Error> {
-        // We generate a random public key as owner since this is used for dry-run only
-        let mut is_original_data = true;
+        let (data_map, blob, _) = Self::encrypt_blob(&data, privately_owned).await?;
 
-        let (data_map, blob) = loop {
-            let blob_storage = BlobStorageDryRun::new(privately_owned);
-            let self_encryptor =
-                SelfEncryptor::new(blob_storage, DataMap::None).map_err(Error::SelfEncryption)?;
-            self_encryptor
-                .write(&data, 0)
-                .await
-                .map_err(Error::SelfEncryption)?;
-            let (data_map, _) = self_encryptor
-                .close()
-                .await
-                .map_err(Error::SelfEncryption)?;
+        Ok((data_map, *blob.address()))
+    }
 
-            let blob_content = if is_original_data {
-                is_original_data = false;
-                serialize(&DataMapLevel::Root(data_map.clone()))?
-            } else {
-                serialize(&DataMapLevel::Child(data_map.clone()))?
-            };
+    /// Estimate the cost of storing the given data in a public or private blob, without
+    /// writing to the network.
+    ///
+    /// The data is self encrypted as `store_public_blob` and `store_private_blob` do, then the
+    /// store cost of each of the chunks they would pay for is queried, and summed up. It
+    /// includes the 20% buffer added by `get_store_cost`. The actual cost may differ should the store cost of
+    /// the network change in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let data = b"some data".to_vec();
+    /// let cost = client.estimate_blob_cost(&data, true).await?;
+    /// if cost <= client.get_balance().await? {
+    ///     let _address = client.store_public_blob(&data).await?;
+    /// }
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn estimate_blob_cost(&self, data: &[u8], public: bool) -> Result<Token, Error> {
+        let (_, blob, chunks) = Self::encrypt_blob(data, self.blob_owner(public)).await?;
+        let cmds = blob_write_cmds(blob, chunks);
+
+        debug!(
+            "Estimating the cost of storing {} bytes in {} chunks",
+            data.len(),
+            cmds.len()
+        );
+        let mut nanos: u64 = 0;
+        for cmd in &cmds {
+            let (_, cost, _) = self.get_store_cost(serialize(cmd)?.len() as u64).await?;
+            nanos = nanos.saturating_add(cost.as_nano());
+        }
 
+        Ok(Token::from_nano(nanos))
+    }
+
+    // --------------------------------------------
+    // ---------- Private helpers -----------------
+    // --------------------------------------------
+
+    // Self encrypt the data without writing to the network. Returns the last data map, the
+    // Blob holding it, and the chunks to store for the Blob to be read, those of the data map
+    // included, should it need to be self encrypted in turn.
+    async fn encrypt_blob(
+        data: &[u8],
+        privately_owned: Option<PublicKey>,
+    ) -> Result<(DataMap, Blob, Vec<Blob>), Error> {
+        let (data_map, chunks) = Self::self_encrypt(data, privately_owned).await?;
+
+        Self::pack_data_map(data_map, privately_owned, chunks).await
+    }
+
+    // Put the root data map in a Blob, which is self encrypted in turn, and its data map put
+    // in a Blob, for as long as it is bigger than the network accepts. The chunks are added
+    // to those already to be stored.
+    async fn pack_data_map(
+        mut data_map: DataMap,
+        privately_owned: Option<PublicKey>,
+        mut chunks: Vec<Blob>,
+    ) -> Result<(DataMap, Blob, Vec<Blob>), Error> {
+        let mut level = DataMapLevel::Root(data_map.clone());
+        loop {
+            let blob_content = serialize(&level)?;
             let blob: Blob = if let Some(owner) = privately_owned {
                 PrivateBlob::new(blob_content, owner).into()
             } else {
@@ -282,22 +554,23 @@ impl Client {
 
             // If Blob (data map) is bigger than 1MB we need to break it down
             if blob.validate_size() {
-                break (data_map, blob);
-            } else {
-                data = serialize(&blob)?;
+                return Ok((data_map, blob, chunks));
             }
-        };
 
-        Ok((data_map, *blob.address()))
+            let (child_data_map, child_chunks) =
+                Self::self_encrypt(&serialize(&blob)?, privately_owned).await?;
+            chunks.extend(child_chunks);
+            data_map = child_data_map;
+            level = DataMapLevel::Child(data_map.clone());
+        }
     }
 
-    // --------------------------------------------
-    // ---------- Private helpers -----------------
-    // --------------------------------------------
-
-    // Writes raw data to the network into immutable data chunks
-    async fn write_to_network(&self, data: &[u8], public: bool) -> Result<DataMap, Error> {
-        let blob_storage = BlobStorage::new(self.clone(), public);
+    // Self encrypt the data, returning its data map and the chunks to store
+    async fn self_encrypt(
+        data: &[u8],
+        privately_owned: Option<PublicKey>,
+    ) -> Result<(DataMap, Vec<Blob>), Error> {
+        let blob_storage = BlobStorageDryRun::new(privately_owned);
         let self_encryptor = SelfEncryptor::new(blob_storage.clone(), DataMap::None)
             .map_err(Error::SelfEncryption)?;
 
@@ -310,11 +583,50 @@ impl Client {
             .close()
             .await
             .map_err(Error::SelfEncryption)?;
+
+        Ok((data_map, blob_storage.take_chunks().await))
+    }
+
+    // Self encrypt the data read from the source, feeding it to the self encryptor
+    // READER_BUFFER_SIZE bytes at a time, and putting the chunks in the given storage.
+    // Returns the data map.
+    async fn self_encrypt_into<R>(
+        mut reader: R,
+        blob_storage: BlobStorageUpload,
+    ) -> Result<DataMap, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let self_encryptor =
+            SelfEncryptor::new(blob_storage, DataMap::None).map_err(Error::SelfEncryption)?;
+
+        let mut buffer = vec![0; READER_BUFFER_SIZE];
+        let mut position = 0;
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+
+            self_encryptor
+                .write(&buffer[..read], position)
+                .await
+                .map_err(Error::SelfEncryption)?;
+            position += read;
+            trace!("Self encrypted {} bytes read from source", position);
+        }
+
+        // The storage is dropped along with the self encryptor, which the uploader waits for
+        let (data_map, _) = self_encryptor
+            .close()
+            .await
+            .map_err(Error::SelfEncryption)?;
+
         Ok(data_map)
     }
 
     // This function reads raw data from the network using the data map
-    async fn read_using_data_map(
+    pub(super) async fn read_using_data_map(
         &self,
         data_map: DataMap,
         public: bool,
@@ -322,8 +634,8 @@ impl Client {
         len: Option<usize>,
     ) -> Result<Vec<u8>, Error> {
         let blob_storage = BlobStorage::new(self.clone(), public);
-        let self_encryptor =
-            SelfEncryptor::new(blob_storage, data_map).map_err(Error::SelfEncryption)?;
+        let self_encryptor = SelfEncryptor::new(blob_storage.clone(), data_map.clone())
+            .map_err(Error::SelfEncryption)?;
 
         let length = match len {
             None => self_encryptor.len().await,
@@ -331,6 +643,9 @@ impl Client {
         };
 
         let read_position = position.unwrap_or(0);
+        blob_storage
+            .prefetch(&data_map, read_position, length)
+            .await;
 
         match self_encryptor.read(read_position, length).await {
             Ok(data) => Ok(data),
@@ -349,33 +664,10 @@ impl Client {
         }
     }
 
-    /// Takes the "Root data map" and returns a Blob that is acceptable by the network
-    ///
-    /// If the root data map blob is too big, the whole blob is self-encrypted and the child data map is put into a blob.
-    /// The above step is repeated as many times as required until the blob size is valid.
-    async fn pack(&self, mut contents: Vec<u8>, public: bool) -> Result<Blob, Error> {
-        loop {
-            let data: Blob = if public {
-                PublicBlob::new(contents).into()
-            } else {
-                PrivateBlob::new(contents, self.public_key()).into()
-            };
-
-            // If data map blob is less thatn 1MB return it so it can be directly sent to the network
-            if data.validate_size() {
-                return Ok(data);
-            } else {
-                let serialized_blob = serialize(&data)?;
-                let data_map = self.write_to_network(&serialized_blob, public).await?;
-                contents = serialize(&DataMapLevel::Child(data_map))?;
-            }
-        }
-    }
-
     /// Takes a blob and fetches the data map from it.
     /// If the data map is not the root data map of the user's contents,
     /// the process repeats itself until it obtains the root data map.
-    async fn unpack(&self, mut data: Blob) -> Result<DataMap, Error> {
+    pub(super) async fn unpack(&self, mut data: Blob) -> Result<DataMap, Error> {
         loop {
             let public = data.is_public();
             match deserialize(data.value())? {
@@ -393,6 +685,17 @@ impl Client {
     }
 }
 
+// Commands storing the chunks of self encrypted data, followed by the Blob holding its
+// data map, as sent when storing a Blob. Each of them is paid for on its own, so
+// estimating the cost of storing a Blob requires the store cost of every one of them.
+fn blob_write_cmds(blob: Blob, chunks: Vec<Blob>) -> Vec<DataCmd> {
+    chunks
+        .into_iter()
+        .chain(iter::once(blob))
+        .map(|blob| DataCmd::Blob(BlobWrite::New(blob)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Blob, BlobAddress, Client, DataMap, DataMapLevel, Error};
@@ -403,7 +706,7 @@ mod tests {
     use self_encryption::Storage;
     use sn_data_types::{PrivateBlob, PublicBlob, Token};
     use sn_messaging::client::Error as ErrorMessage;
-    use std::str::FromStr;
+    use std::{iter, str::FromStr};
 
     // Test putting and getting pub Blob.
     #[tokio::test]
@@ -624,6 +927,66 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    pub async fn blob_upload_is_paid_chunk_by_chunk() -> Result<()> {
+        let mut client = create_test_client().await?;
+        client.set_chunk_concurrency(2);
+        let value = generate_random_vector::<u8>(5 * 1024 * 1024);
+
+        let address = client.store_private_blob(&value).await?;
+
+        // each of the 5 chunks, and the Blob holding the data map, were paid for on their own
+        let history = client.get_transfer_history(client.public_key(), 0).await?;
+        assert_eq!(history.debits.len(), 6);
+
+        let fetched_data = retry_loop!(client.read_blob(address, None, None));
+        assert_eq!(fetched_data, value);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn estimated_blob_cost_matches_cost_paid() -> Result<()> {
+        let client = create_test_client().await?;
+        let value = generate_random_vector::<u8>(1024 * 1024);
+
+        let estimate = client.estimate_blob_cost(&value, false).await?;
+        assert_ne!(estimate, Token::from_nano(0));
+        // nothing was paid for the estimate
+        let balance_before = client.get_local_balance().await;
+        assert_eq!(balance_before, Token::from_str("10")?);
+
+        let _ = client.store_private_blob(&value).await?;
+        let balance_after = client.get_local_balance().await;
+        assert_eq!(
+            balance_after,
+            Token::from_nano(balance_before.as_nano() - estimate.as_nano())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn estimated_blob_cost_matches_cost_paid_beyond_32_chunks() -> Result<()> {
+        let client = create_test_client().await?;
+        let value = generate_random_vector::<u8>(33 * 1024 * 1024);
+
+        let estimate = client.estimate_blob_cost(&value, true).await?;
+        let balance_before = client.get_local_balance().await;
+
+        let _ = client.store_public_blob(&value).await?;
+        let balance_after = client.get_local_balance().await;
+        assert_eq!(
+            balance_after,
+            Token::from_nano(balance_before.as_nano() - estimate.as_nano())
+        );
+        // a payment was made for each of the 33 chunks, and the Blob holding the data map
+        let history = client.get_transfer_history(client.public_key(), 0).await?;
+        assert_eq!(history.debits.len(), 34);
+
+        Ok(())
+    }
+
     #[tokio::test]
     pub async fn create_and_retrieve_1mb_public() -> Result<()> {
         let size = 1024 * 1024;
@@ -656,6 +1019,42 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    pub async fn store_blob_from_reader_reports_progress() -> Result<()> {
+        let size = 1024 * 1024 * 3 + 10;
+        let data = generate_random_vector::<u8>(size);
+        let client = create_test_client().await?;
+
+        let mut progress = Vec::new();
+        let address = client
+            .store_blob_from_reader_with_progress(data.as_slice(), false, |stored| {
+                progress.push(stored)
+            })
+            .await?;
+
+        // progress is reported once for each chunk stored, and for the data map
+        let (_, blob, chunks) = Client::encrypt_blob(&data, Some(client.public_key())).await?;
+        assert_eq!(progress.len(), chunks.len() + 1);
+        let total: u64 = chunks
+            .iter()
+            .chain(iter::once(&blob))
+            .map(|blob| blob.value().len() as u64)
+            .sum();
+        assert_eq!(progress[progress.len() - 1], total);
+        assert!(total >= size as u64);
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // the address is the same as when storing the data from memory
+        let (_, expected_address) =
+            Client::blob_data_map(data.clone(), Some(client.public_key())).await?;
+        assert_eq!(address, expected_address);
+
+        let fetched_data = retry_loop!(client.read_blob(address, None, None));
+        assert_eq!(fetched_data, data);
+
+        Ok(())
+    }
+
     #[tokio::test]
     pub async fn create_and_retrieve_index_based() -> Result<()> {
         create_and_index_based_retrieve(1024).await
@@ -0,0 +1,270 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{blob_storage::BlobStorage, Client};
+use crate::Error;
+use futures::future::BoxFuture;
+use log::trace;
+use self_encryption::{DataMap, SelfEncryptor};
+use sn_data_types::BlobAddress;
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+// Number of bytes fetched from the network at a time, which matches the maximum size of a chunk
+const READ_SEGMENT_SIZE: u64 = 1024 * 1024;
+
+/// Reader over the contents of a Blob, obtained with `Client::blob_reader`.
+///
+/// The chunks the Blob is made of are only fetched and decrypted as the contents are read,
+/// holding at most one segment of the contents in memory at a time.
+pub struct BlobReader {
+    client: Client,
+    data_map: DataMap,
+    public: bool,
+    len: u64,
+    position: u64,
+    // Contents last fetched, starting at `buffer_start`
+    buffer: Vec<u8>,
+    buffer_start: u64,
+    // Fetch in progress, along with the position it starts at
+    pending: Option<(u64, BoxFuture<'static, Result<Vec<u8>, Error>>)>,
+}
+
+impl Client {
+    /// Open a reader over the contents of a Blob, which implements `AsyncRead` and `AsyncSeek`.
+    ///
+    /// Unlike `read_blob`, the contents are fetched lazily as they are read, so large Blobs can
+    /// be written to disk or sent elsewhere without being held in memory as a whole.
+    ///
+    /// # Examples
+    ///
+    /// Read the second half of a Blob
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::BlobAddress;
+    /// use std::io::SeekFrom;
+    /// use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// let target_blob = BlobAddress::Public(XorName::random());
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    ///
+    /// let mut reader = client.blob_reader(target_blob).await?;
+    /// let half = reader.len() / 2;
+    /// let _ = reader.seek(SeekFrom::Start(half)).await?;
+    /// let mut second_half = Vec::new();
+    /// let _ = reader.read_to_end(&mut second_half).await?;
+    /// # Ok(())} );}
+    /// ```
+    pub async fn blob_reader(&self, address: BlobAddress) -> Result<BlobReader, Error> {
+        trace!("Opening reader for Blob: {:?}", address);
+
+        let data = self.fetch_blob_from_network(address).await?;
+        let public = address.is_public();
+        let data_map = self.unpack(data).await?;
+
+        let self_encryptor =
+            SelfEncryptor::new(BlobStorage::new(self.clone(), public), data_map.clone())
+                .map_err(Error::SelfEncryption)?;
+        let len = self_encryptor.len().await as u64;
+
+        Ok(BlobReader {
+            client: self.clone(),
+            data_map,
+            public,
+            len,
+            position: 0,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            pending: None,
+        })
+    }
+}
+
+impl BlobReader {
+    /// Length of the contents of the Blob.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the Blob has no contents.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Contents already fetched from the current position onwards, if any
+    fn buffered(&self) -> Option<&[u8]> {
+        let buffer_end = self.buffer_start + self.buffer.len() as u64;
+        if self.position >= self.buffer_start && self.position < buffer_end {
+            Some(&self.buffer[(self.position - self.buffer_start) as usize..])
+        } else {
+            None
+        }
+    }
+
+    fn fetch(&self, start: u64) -> BoxFuture<'static, Result<Vec<u8>, Error>> {
+        let client = self.client.clone();
+        let data_map = self.data_map.clone();
+        let public = self.public;
+        let len = READ_SEGMENT_SIZE.min(self.len - start);
+        trace!("Fetching {} bytes of Blob from position {}", len, start);
+
+        Box::pin(async move {
+            client
+                .read_using_data_map(data_map, public, Some(start as usize), Some(len as usize))
+                .await
+        })
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(bytes) = this.buffered() {
+                let count = bytes.len().min(buf.remaining());
+                buf.put_slice(&bytes[..count]);
+                this.position += count as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.position >= this.len {
+                return Poll::Ready(Ok(()));
+            }
+
+            // A seek may have happened since the pending fetch was started
+            let start = this.position;
+            let mut fetch = match this.pending.take() {
+                Some((pending_start, fetch)) if pending_start == start => fetch,
+                _ => this.fetch(start),
+            };
+
+            let data = match fetch.as_mut().poll(cx) {
+                Poll::Pending => {
+                    this.pending = Some((start, fetch));
+                    return Poll::Pending;
+                }
+                Poll::Ready(result) => {
+                    result.map_err(|error| io::Error::new(io::ErrorKind::Other, error))?
+                }
+            };
+
+            if data.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Blob contents ended before their expected length",
+                )));
+            }
+            this.buffer = data;
+            this.buffer_start = start;
+        }
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let new_position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_position(this.len, offset),
+            SeekFrom::Current(offset) => offset_position(this.position, offset),
+        };
+
+        this.position = new_position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+fn offset_position(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.checked_neg()? as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::retry_loop;
+    use crate::utils::{generate_random_vector, test_utils::create_test_client};
+    use anyhow::Result;
+    use std::io::SeekFrom;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    #[tokio::test]
+    pub async fn blob_reader_reads_whole_blob() -> Result<()> {
+        let size = 1024 * 1024 * 3 + 10;
+        let data = generate_random_vector::<u8>(size);
+        let client = create_test_client().await?;
+
+        let address = client.store_public_blob(&data).await?;
+        let mut reader = retry_loop!(client.blob_reader(address));
+        assert_eq!(reader.len(), size as u64);
+
+        let mut fetched_data = Vec::new();
+        let _ = reader.read_to_end(&mut fetched_data).await?;
+        assert_eq!(fetched_data, data);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn blob_reader_seeks() -> Result<()> {
+        let size = 1024 * 1024 * 2;
+        let data = generate_random_vector::<u8>(size);
+        let client = create_test_client().await?;
+
+        let address = client.store_private_blob(&data).await?;
+        let mut reader = retry_loop!(client.blob_reader(address));
+
+        let position = reader.seek(SeekFrom::Start(size as u64 / 2 + 5)).await?;
+        assert_eq!(position, size as u64 / 2 + 5);
+        let mut fetched_data = vec![0; 100];
+        reader.read_exact(&mut fetched_data).await?;
+        assert_eq!(fetched_data, data[size / 2 + 5..size / 2 + 105]);
+
+        let _ = reader.seek(SeekFrom::End(-10)).await?;
+        let mut fetched_data = Vec::new();
+        let _ = reader.read_to_end(&mut fetched_data).await?;
+        assert_eq!(fetched_data, data[size - 10..]);
+
+        let _ = reader.seek(SeekFrom::Current(-20)).await?;
+        let mut fetched_data = vec![0; 20];
+        reader.read_exact(&mut fetched_data).await?;
+        assert_eq!(fetched_data, data[size - 20..]);
+
+        assert!(reader
+            .seek(SeekFrom::Current(-(size as i64) - 1))
+            .await
+            .is_err());
+
+        Ok(())
+    }
+}
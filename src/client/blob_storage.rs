@@ -8,67 +8,156 @@
 
 use super::Client;
 use async_trait::async_trait;
-use log::trace;
-use self_encryption::{SelfEncryptionError, Storage};
+use futures::stream::{self, StreamExt};
+use log::{debug, trace, warn};
+use self_encryption::{DataMap, SelfEncryptionError, Storage};
 use sn_data_types::{Blob, BlobAddress, PrivateBlob, PublicBlob, PublicKey};
+use std::{collections::HashMap, mem, sync::Arc};
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    Mutex,
+};
 use xor_name::{XorName, XOR_NAME_LEN};
 
 /// Network storage is the concrete type which self_encryption crate will use
 /// to put or get data from the network.
+///
+/// Chunks are prefetched up to the client's chunk concurrency limit at a time.
 #[derive(Clone)]
 pub struct BlobStorage {
     client: Client,
     public: bool,
+    prefetched: Arc<Mutex<HashMap<XorName, Vec<u8>>>>,
 }
 
 impl BlobStorage {
     /// Create a new BlobStorage instance.
     pub fn new(client: Client, public: bool) -> Self {
-        Self { client, public }
+        Self {
+            client,
+            public,
+            prefetched: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Fetch concurrently the chunks holding the given range of the data, so that they
+    /// are readily available when self encryption gets them.
+    pub async fn prefetch(&self, data_map: &DataMap, position: usize, len: usize) {
+        let chunks = match data_map {
+            DataMap::Chunks(chunks) => chunks,
+            _ => return,
+        };
+
+        let end = position.saturating_add(len);
+        let mut offset = 0;
+        let mut names = Vec::new();
+        for chunk in chunks {
+            let chunk_end = offset + chunk.source_size as usize;
+            if chunk_end > position && offset < end {
+                if let Ok(name) = chunk_name(&chunk.hash) {
+                    let is_cached = match &self.client.chunk_cache {
+                        Some(cache) => cache.contains(&name),
+                        None => false,
+                    };
+                    if !is_cached {
+                        names.push(name);
+                    }
+                }
+            }
+            offset = chunk_end;
+        }
+
+        // Nothing to gain from fetching a single chunk ahead of time
+        if names.len() < 2 {
+            return;
+        }
+
+        trace!("Prefetching {} chunks", names.len());
+        let fetched: Vec<_> = stream::iter(names)
+            .map(|name| async move {
+                let result = self
+                    .client
+                    .fetch_blob_from_network(self.address(name))
+                    .await;
+                (name, result)
+            })
+            .buffer_unordered(self.client.chunk_concurrency)
+            .collect()
+            .await;
+
+        let mut prefetched = self.prefetched.lock().await;
+        for (name, result) in fetched {
+            match result {
+                Ok(blob) => {
+                    let _ = prefetched.insert(name, blob.value().clone());
+                }
+                // It'll be fetched again, and the error surfaced, when self encryption gets it
+                Err(error) => debug!("Failed to prefetch chunk {:?}: {:?}", name, error),
+            }
+        }
+    }
+
+    // Get a chunk from the client's cache, if any, checking it still matches its name
+    async fn get_from_cache(&self, name: &XorName) -> Option<Vec<u8>> {
+        let cache = self.client.chunk_cache.as_ref()?;
+        let data = cache.get(name).await?;
+        match self.generate_address(&data).await {
+            Ok(address) if address == name.0 => Some(data),
+            _ => {
+                warn!("Cached chunk {:?} is corrupted, discarding it", name);
+                cache.remove(name).await;
+                None
+            }
+        }
+    }
+
+    fn address(&self, name: XorName) -> BlobAddress {
+        if self.public {
+            BlobAddress::Public(name)
+        } else {
+            BlobAddress::Private(name)
+        }
     }
 }
 
 #[async_trait]
 impl Storage for BlobStorage {
     async fn get(&mut self, name: &[u8]) -> Result<Vec<u8>, SelfEncryptionError> {
-        if name.len() != XOR_NAME_LEN {
-            return Err(SelfEncryptionError::Generic(
-                "Requested `name` is incorrect size.".to_owned(),
-            ));
+        let name = chunk_name(name)?;
+
+        if let Some(data) = self.get_from_cache(&name).await {
+            trace!("Self encrypt got cached chunk {:?}", name);
+            return Ok(data);
         }
 
-        let name = {
-            let mut temp = [0_u8; XOR_NAME_LEN];
-            temp.clone_from_slice(name);
-            XorName(temp)
-        };
+        let prefetched = self.prefetched.lock().await.remove(&name);
+        let data = match prefetched {
+            Some(data) => {
+                trace!("Self encrypt got prefetched chunk {:?}", name);
+                data
+            }
+            None => {
+                let address = self.address(name);
+                trace!("Self encrypt invoked GetBlob({:?})", &address);
 
-        let address = if self.public {
-            BlobAddress::Public(name)
-        } else {
-            BlobAddress::Private(name)
+                match self.client.fetch_blob_from_network(address).await {
+                    Ok(data) => data.value().clone(),
+                    Err(error) => return Err(SelfEncryptionError::Generic(format!("{}", error))),
+                }
+            }
         };
 
-        trace!("Self encrypt invoked GetBlob({:?})", &address);
-
-        match self.client.fetch_blob_from_network(address).await {
-            Ok(data) => Ok(data.value().clone()),
-            Err(error) => Err(SelfEncryptionError::Generic(format!("{}", error))),
+        if let Some(cache) = &self.client.chunk_cache {
+            if let Err(error) = cache.put(name, &data).await {
+                warn!("Failed to add chunk {:?} to cache: {:?}", name, error);
+            }
         }
+
+        Ok(data)
     }
 
     async fn delete(&mut self, name: &[u8]) -> Result<(), SelfEncryptionError> {
-        if name.len() != XOR_NAME_LEN {
-            return Err(SelfEncryptionError::Generic(
-                "Requested `name` is incorrect size.".to_owned(),
-            ));
-        }
-
-        let name = {
-            let mut temp = [0_u8; XOR_NAME_LEN];
-            temp.clone_from_slice(name);
-            XorName(temp)
-        };
+        let name = chunk_name(name)?;
 
         let address = if self.public {
             return Err(SelfEncryptionError::Generic(
@@ -79,6 +168,10 @@ impl Storage for BlobStorage {
         };
         trace!("Self encrypt invoked DeleteBlob({:?})", &address);
 
+        if let Some(cache) = &self.client.chunk_cache {
+            cache.remove(&name).await;
+        }
+
         match self.client.delete_blob_from_network(address).await {
             Ok(_) => Ok(()),
             Err(error) => Err(SelfEncryptionError::Generic(format!("{}", error))),
@@ -108,17 +201,41 @@ impl Storage for BlobStorage {
     }
 }
 
-/// Network storage is the concrete type which self_encryption crate will use
-/// to put or get data from the network.
+// Name of the Blob holding a chunk, as given by self encryption
+fn chunk_name(name: &[u8]) -> Result<XorName, SelfEncryptionError> {
+    if name.len() != XOR_NAME_LEN {
+        return Err(SelfEncryptionError::Generic(
+            "Requested `name` is incorrect size.".to_owned(),
+        ));
+    }
+
+    let mut temp = [0_u8; XOR_NAME_LEN];
+    temp.clone_from_slice(name);
+    Ok(XorName(temp))
+}
+
+/// Storage which self_encryption crate can use without connecting to the network.
+///
+/// Nothing is stored, but the chunks put are kept, so they can be stored, or the cost of
+/// storing them worked out, once self encryption is done.
 #[derive(Clone)]
 pub struct BlobStorageDryRun {
     privately_owned: Option<PublicKey>,
+    chunks: Arc<Mutex<Vec<Blob>>>,
 }
 
 impl BlobStorageDryRun {
     /// Create a new BlobStorage instance.
     pub fn new(privately_owned: Option<PublicKey>) -> Self {
-        Self { privately_owned }
+        Self {
+            privately_owned,
+            chunks: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Take the Blobs holding the chunks put so far, in the order they were put.
+    pub async fn take_chunks(&self) -> Vec<Blob> {
+        mem::take(&mut *self.chunks.lock().await)
     }
 }
 
@@ -131,10 +248,17 @@ impl Storage for BlobStorageDryRun {
         ))
     }
 
-    async fn put(&mut self, _: Vec<u8>, _data: Vec<u8>) -> Result<(), SelfEncryptionError> {
+    async fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Result<(), SelfEncryptionError> {
         trace!("Self encrypt invoked PutBlob dry run.");
-        // We do nothing here just return ok so self_encrpytion can finish
+        // We only keep the chunk, so self_encrpytion can finish
         // and generate chunk addresses and datamap if required
+        let blob: Blob = if let Some(owner) = self.privately_owned {
+            PrivateBlob::new(data, owner).into()
+        } else {
+            PublicBlob::new(data).into()
+        };
+        self.chunks.lock().await.push(blob);
+
         Ok(())
     }
 
@@ -154,3 +278,142 @@ impl Storage for BlobStorageDryRun {
         Ok(blob.name().0.to_vec())
     }
 }
+
+/// Storage which self_encryption crate can use to store chunks on the network as it puts them.
+///
+/// The chunks put are handed over to the uploader receiving them, through a channel holding
+/// a bounded number of them: once it is full, putting a chunk waits for the uploader to take
+/// one, so chunks don't pile up in memory faster than they are stored.
+#[derive(Clone)]
+pub struct BlobStorageUpload {
+    privately_owned: Option<PublicKey>,
+    chunks: Sender<Blob>,
+}
+
+impl BlobStorageUpload {
+    /// Create a new BlobStorageUpload instance, along with the receiver of the chunks put,
+    /// of which at most `capacity` are held at a time.
+    pub fn new(privately_owned: Option<PublicKey>, capacity: usize) -> (Self, Receiver<Blob>) {
+        let (chunks, receiver) = mpsc::channel(capacity.max(1));
+        let storage = Self {
+            privately_owned,
+            chunks,
+        };
+
+        (storage, receiver)
+    }
+}
+
+#[async_trait]
+impl Storage for BlobStorageUpload {
+    async fn get(&mut self, _name: &[u8]) -> Result<Vec<u8>, SelfEncryptionError> {
+        trace!("Self encrypt invoked GetBlob on an upload.");
+        Err(SelfEncryptionError::Generic(
+            "Cannot get from storage since it's an upload.".to_owned(),
+        ))
+    }
+
+    async fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Result<(), SelfEncryptionError> {
+        let blob: Blob = if let Some(owner) = self.privately_owned {
+            PrivateBlob::new(data, owner).into()
+        } else {
+            PublicBlob::new(data).into()
+        };
+        trace!(
+            "Self encrypt invoked PutBlob({:?}) on an upload",
+            blob.address()
+        );
+
+        self.chunks.send(blob).await.map_err(|_| {
+            SelfEncryptionError::Generic("The upload of the chunks has stopped.".to_owned())
+        })
+    }
+
+    async fn delete(&mut self, _name: &[u8]) -> Result<(), SelfEncryptionError> {
+        trace!("Self encrypt invoked DeleteBlob on an upload.");
+
+        Ok(())
+    }
+
+    async fn generate_address(&self, data: &[u8]) -> Result<Vec<u8>, SelfEncryptionError> {
+        let blob: Blob = if let Some(owner) = self.privately_owned {
+            PrivateBlob::new(data.to_vec(), owner).into()
+        } else {
+            PublicBlob::new(data.to_vec()).into()
+        };
+
+        Ok(blob.name().0.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        retry_loop,
+        utils::{generate_random_vector, test_utils::create_test_client},
+    };
+    use anyhow::{bail, Result};
+    use futures::FutureExt;
+    use self_encryption::SelfEncryptor;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    pub async fn chunks_read_together_are_prefetched() -> Result<()> {
+        let mut client = create_test_client().await?;
+        // a limit of zero is raised to one chunk at a time
+        client.set_chunk_concurrency(0);
+        let value = generate_random_vector::<u8>(4 * 1024 * 1024);
+        let address = client.store_public_blob(&value).await?;
+        let _ = retry_loop!(client.read_blob(address, None, None));
+
+        let (data_map, _) = Client::blob_data_map(value.clone(), None).await?;
+        let chunks = match &data_map {
+            DataMap::Chunks(chunks) => chunks.clone(),
+            other => bail!("Unexpected data map: {:?}", other),
+        };
+        let storage = BlobStorage::new(client, true);
+
+        // nothing is gained from prefetching a single chunk
+        storage.prefetch(&data_map, 0, 1).await;
+        assert!(storage.prefetched.lock().await.is_empty());
+
+        storage.prefetch(&data_map, 0, value.len()).await;
+        assert_eq!(storage.prefetched.lock().await.len(), chunks.len());
+
+        // self encryption then gets each chunk from those prefetched, only once
+        let name = chunk_name(&chunks[0].hash)?;
+        let prefetched = storage.prefetched.lock().await.get(&name).cloned();
+        let data = storage.clone().get(&chunks[0].hash).await?;
+        assert_eq!(Some(data), prefetched);
+        assert!(!storage.prefetched.lock().await.contains_key(&name));
+        assert_eq!(storage.prefetched.lock().await.len(), chunks.len() - 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn chunks_put_for_upload_wait_once_the_channel_is_full() -> Result<()> {
+        let (storage, mut chunks) = BlobStorageUpload::new(None, 2);
+        // enough data for self encryption to put 5 chunks
+        let value = generate_random_vector::<u8>(5 * 1024 * 1024);
+        let encrypt = async move {
+            let self_encryptor = SelfEncryptor::new(storage, DataMap::None)?;
+            self_encryptor.write(&value, 0).await?;
+            self_encryptor.close().await
+        };
+
+        // nothing takes the chunks, so self encryption can't get done putting them
+        assert!(timeout(Duration::from_secs(10), encrypt).await.is_err());
+
+        // and no more chunks than the capacity of the channel were held meanwhile
+        let mut held = 0;
+        while let Some(Some(_)) = chunks.recv().now_or_never() {
+            held += 1;
+        }
+        assert_eq!(held, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{blocking, ChunkCache, Client, ReplicaStore};
+use crate::{
+    config_handler::{ClientConfig, Config},
+    connections::transport::{QuicP2pTransport, Transport},
+    Error,
+};
+use log::debug;
+use sn_data_types::Keypair;
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Build a Safe Network client, with settings left out taking their default value.
+///
+/// # Examples
+///
+/// Create a client for an existing keypair, which fails fast when elders don't respond
+/// ```no_run
+/// # extern crate tokio; use anyhow::Result;
+/// # use sn_client::utils::test_utils::read_network_conn_info;
+/// use sn_client::{config_handler::ClientConfig, ClientBuilder};
+/// use sn_data_types::Keypair;
+/// use rand::rngs::OsRng;
+/// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+/// # let bootstrap_contacts = read_network_conn_info()?;
+/// let client = ClientBuilder::new()
+///     .keypair(Keypair::new_ed25519(&mut OsRng))
+///     .bootstrap_contacts(bootstrap_contacts)
+///     .client_config(ClientConfig {
+///         query_timeout_secs: 10,
+///         send_retries: 0,
+///         ..Default::default()
+///     })
+///     .build()
+///     .await?;
+/// let _some_balance = client.get_balance().await?;
+/// # Ok(()) } ); }
+/// ```
+pub struct ClientBuilder {
+    keypair: Option<Keypair>,
+    config_file_path: Option<PathBuf>,
+    bootstrap_contacts: Option<HashSet<SocketAddr>>,
+    transport: Option<Arc<dyn Transport>>,
+    client_config: Option<ClientConfig>,
+    chunk_cache: Option<(PathBuf, u64)>,
+    replica_store_dir: Option<PathBuf>,
+    simulated_payout: bool,
+    read_only: bool,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            keypair: None,
+            config_file_path: None,
+            bootstrap_contacts: None,
+            transport: None,
+            client_config: None,
+            chunk_cache: None,
+            replica_store_dir: None,
+            simulated_payout: true,
+            read_only: false,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Start building a client with the default settings, i.e. a random keypair, the default
+    /// config, and no caches.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given keypair, rather than a random one. The client retrieves the history of
+    /// the key's balance when built.
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
+    /// Read the config from the given file, rather than using the default config.
+    pub fn config_file_path(mut self, path: &Path) -> Self {
+        self.config_file_path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Bootstrap to the given contacts, rather than to those of the config.
+    pub fn bootstrap_contacts(mut self, contacts: HashSet<SocketAddr>) -> Self {
+        self.bootstrap_contacts = Some(contacts);
+        self
+    }
+
+    /// Talk to the network over the given transport, rather than over qp2p. The qp2p part of
+    /// the config, and the bootstrap contacts, are then left unused.
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Use the given timeouts and retry policy, rather than those of the config.
+    pub fn client_config(mut self, config: ClientConfig) -> Self {
+        self.client_config = Some(config);
+        self
+    }
+
+    /// Cache the chunks read in the given directory, holding up to `max_bytes` of them. See
+    /// `Client::set_chunk_cache`.
+    pub fn chunk_cache_dir(mut self, dir: &Path, max_bytes: u64) -> Self {
+        self.chunk_cache = Some((dir.to_path_buf(), max_bytes));
+        self
+    }
+
+    /// Keep the local replicas of Sequences and Registers in the given directory, so they
+    /// outlive the client. See `Client::set_replica_store`.
+    pub fn replica_store_dir(mut self, dir: &Path) -> Self {
+        self.replica_store_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Whether a random client gets a simulated farming payout of 10 tokens when built, which
+    /// is the case by default. It has no effect unless the `simulated-payouts` feature is
+    /// enabled, nor for clients built with a keypair.
+    pub fn simulated_payout(mut self, enabled: bool) -> Self {
+        self.simulated_payout = enabled;
+        self
+    }
+
+    /// Whether the client is read-only, which it isn't by default. A read-only client only
+    /// bootstraps and reads from the network: no transfer actor is set up for its keypair, it
+    /// gets no simulated payout, and all writes, token transfers included, fail with
+    /// `Error::ReadOnlyClient` before any network work.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Bootstrap the client to the network.
+    pub async fn build(self) -> Result<Client, Error> {
+        let Config { mut qp2p, client } =
+            Config::new(self.config_file_path.as_deref(), self.bootstrap_contacts);
+        let client_config = self.client_config.unwrap_or(client);
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                // We use feature `no-igd` so this will use the echo service only
+                qp2p.forward_port = true;
+                Arc::new(QuicP2pTransport::new(qp2p)?)
+            }
+        };
+
+        // Open the caches first, so we don't bootstrap for nothing should they fail to open
+        let chunk_cache = match self.chunk_cache {
+            Some((dir, max_bytes)) => {
+                debug!("Caching chunks in {}", dir.display());
+                Some(blocking(move || ChunkCache::load(&dir, max_bytes)).await?)
+            }
+            None => None,
+        };
+        let replica_store = match self.replica_store_dir {
+            Some(dir) => {
+                debug!("Keeping replicas in {}", dir.display());
+                Some(ReplicaStore::open(&dir)?)
+            }
+            None => None,
+        };
+
+        let mut client = Client::connect(
+            self.keypair,
+            transport,
+            client_config,
+            self.simulated_payout,
+            self.read_only,
+        )
+        .await?;
+
+        if let Some(cache) = chunk_cache {
+            client.set_chunk_cache(cache);
+        }
+        if let Some(store) = replica_store {
+            client.set_replica_store(store);
+        }
+
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{utils::test_utils::test_client_builder, Error};
+    use anyhow::{bail, Result};
+    use rand::rngs::OsRng;
+    use sn_data_types::{Keypair, Token};
+
+    #[tokio::test]
+    pub async fn client_built_without_simulated_payout() -> Result<()> {
+        let client = test_client_builder()
+            .await?
+            .simulated_payout(false)
+            .build()
+            .await?;
+
+        assert_eq!(client.get_local_balance().await, Token::from_nano(0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn read_only_client_rejects_writes() -> Result<()> {
+        let client = test_client_builder().await?.read_only(true).build().await?;
+        assert!(client.is_read_only());
+        assert_eq!(client.get_local_balance().await, Token::from_nano(0));
+
+        match client.store_public_blob(b"some data").await {
+            Err(Error::ReadOnlyClient) => (),
+            other => bail!("Unexpected result: {:?}", other),
+        }
+        let recipient = Keypair::new_ed25519(&mut OsRng).public_key();
+        match client.send_tokens(recipient, Token::from_nano(1)).await {
+            Err(Error::ReadOnlyClient) => (),
+            other => bail!("Unexpected result: {:?}", other),
+        }
+        // there is no transfer actor to sync the history to
+        match client.get_history().await {
+            Err(Error::ReadOnlyClient) => (),
+            other => bail!("Unexpected result: {:?}", other),
+        }
+
+        Ok(())
+    }
+}
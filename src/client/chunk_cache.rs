@@ -0,0 +1,422 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::blocking;
+use crate::Error;
+use log::{debug, trace, warn};
+use rand::random;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// On-disk cache of chunks, keyed by the name of the Blob holding them.
+///
+/// Chunks are immutable, so once fetched they can be read from the cache rather than the
+/// network. The least recently used chunks are evicted once the cache grows over its size limit.
+/// The modification time of a chunk's file is updated on every use, so that the order the
+/// chunks were used in is kept across runs.
+pub struct ChunkCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<XorName, Entry>,
+    by_last_use: BTreeMap<u64, XorName>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+struct Entry {
+    size: u64,
+    last_use: u64,
+}
+
+impl CacheState {
+    // Record a use of the entry, inserting it if needed
+    fn touch(&mut self, name: XorName, size: u64) {
+        self.clock += 1;
+        let last_use = self.clock;
+        if let Some(previous) = self.entries.insert(name, Entry { size, last_use }) {
+            let _ = self.by_last_use.remove(&previous.last_use);
+            self.total_bytes -= previous.size;
+        }
+        let _ = self.by_last_use.insert(last_use, name);
+        self.total_bytes += size;
+    }
+
+    fn remove(&mut self, name: &XorName) -> bool {
+        match self.entries.remove(name) {
+            Some(entry) => {
+                let _ = self.by_last_use.remove(&entry.last_use);
+                self.total_bytes -= entry.size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Remove the least recently used entries until the cache fits within `max_bytes`,
+    // returning the names of those whose files are to be removed
+    fn evict(&mut self, max_bytes: u64) -> Vec<XorName> {
+        let mut evicted = Vec::new();
+        while self.total_bytes > max_bytes {
+            let name = match self.by_last_use.values().next() {
+                Some(name) => *name,
+                None => break,
+            };
+            trace!("Evicting chunk {:?} from cache", name);
+            let _ = self.remove(&name);
+            evicted.push(name);
+        }
+        evicted
+    }
+}
+
+impl ChunkCache {
+    /// Open the cache held in the given directory, creating it if needed, which won't grow
+    /// over `max_bytes`. Chunks cached by a previous run are kept, in the order they were used.
+    ///
+    /// This reads the whole directory, so from async code it's best run as a blocking task.
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self, Error> {
+        Ok(Self::load(dir, max_bytes)?)
+    }
+
+    // Open the cache, as `open` does, failing only with the I/O error met
+    pub(crate) fn load(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut existing = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let entry_name = entry.file_name();
+            let entry_name = match entry_name.to_str() {
+                Some(entry_name) => entry_name,
+                None => continue,
+            };
+            // Chunks whose writing was interrupted by the end of a previous run
+            if entry_name.ends_with(".tmp") {
+                trace!("Removing stale chunk file {} from cache", entry_name);
+                if let Err(error) = fs::remove_file(entry.path()) {
+                    warn!(
+                        "Failed to remove stale chunk file {}: {:?}",
+                        entry_name, error
+                    );
+                }
+                continue;
+            }
+            let name = match name_from_file(entry_name) {
+                Some(name) => name,
+                None => continue,
+            };
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            existing.push((modified, name, metadata.len()));
+        }
+        existing.sort();
+
+        let mut state = CacheState::default();
+        for (_, name, size) in existing {
+            state.touch(name, size);
+        }
+        let evicted = state.evict(max_bytes);
+        debug!(
+            "Opened chunk cache at {} holding {} bytes",
+            dir.display(),
+            state.total_bytes
+        );
+        for name in evicted {
+            if let Err(error) = fs::remove_file(dir.join(file_name(&name))) {
+                warn!("Failed to remove chunk {:?} from cache: {:?}", name, error);
+            }
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Get a chunk from the cache, if it's there.
+    pub async fn get(&self, name: &XorName) -> Option<Vec<u8>> {
+        if !self.contains(name) {
+            return None;
+        }
+
+        let path = self.path(name);
+        let read = blocking(move || {
+            let data = fs::read(&path)?;
+            touch(&path);
+            Ok(data)
+        });
+        match read.await {
+            Ok(data) => {
+                trace!("Chunk {:?} found in cache", name);
+                let mut state = self.lock();
+                // It may have been evicted while being read
+                if state.entries.contains_key(name) {
+                    state.touch(*name, data.len() as u64);
+                }
+                Some(data)
+            }
+            Err(error) => {
+                warn!("Failed to read chunk {:?} from cache: {:?}", name, error);
+                let _ = self.lock().remove(name);
+                None
+            }
+        }
+    }
+
+    /// Whether a chunk is held in the cache.
+    pub fn contains(&self, name: &XorName) -> bool {
+        self.lock().entries.contains_key(name)
+    }
+
+    /// Add a chunk to the cache, evicting the least recently used ones if needed.
+    pub async fn put(&self, name: XorName, data: &[u8]) -> Result<(), Error> {
+        let size = data.len() as u64;
+        if size > self.max_bytes {
+            return Ok(());
+        }
+
+        let path = self.path(&name);
+        if self.contains(&name) {
+            blocking(move || {
+                touch(&path);
+                Ok(())
+            })
+            .await?;
+        } else {
+            // Write to a temporary file first so that no partial chunk is ever picked up. Its
+            // name is unique, should the same chunk be put concurrently.
+            let tmp_path =
+                self.dir
+                    .join(format!("{}.{:016x}.tmp", file_name(&name), random::<u64>()));
+            let data = data.to_vec();
+            blocking(move || {
+                fs::write(&tmp_path, data)?;
+                fs::rename(&tmp_path, path)
+            })
+            .await?;
+        }
+
+        let evicted = {
+            let mut state = self.lock();
+            state.touch(name, size);
+            state.evict(self.max_bytes)
+        };
+        self.remove_files(evicted).await;
+
+        Ok(())
+    }
+
+    /// Remove a chunk from the cache.
+    pub async fn remove(&self, name: &XorName) {
+        let removed = self.lock().remove(name);
+        if removed {
+            self.remove_files(vec![*name]).await;
+        }
+    }
+
+    /// Number of bytes of chunks held in the cache.
+    pub fn size(&self) -> u64 {
+        self.lock().total_bytes
+    }
+
+    // Remove the files of chunks no longer held in the cache
+    async fn remove_files(&self, names: Vec<XorName>) {
+        if names.is_empty() {
+            return;
+        }
+
+        let paths: Vec<_> = names.iter().map(|name| self.path(name)).collect();
+        let result = blocking(move || {
+            for path in paths {
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(error) = result {
+            warn!(
+                "Failed to remove chunks {:?} from cache: {:?}",
+                names, error
+            );
+        }
+    }
+
+    fn path(&self, name: &XorName) -> PathBuf {
+        self.dir.join(file_name(name))
+    }
+
+    // Only the index of the cache is held under the lock, the files are accessed without it
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Record a use of the chunk's file, for the order of use to be restored by the next run.
+// Failing to only makes the chunk more likely to be evicted then, so it's not reported.
+fn touch(path: &Path) {
+    let result = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(error) = result {
+        warn!(
+            "Failed to record the use of {}: {:?}",
+            path.display(),
+            error
+        );
+    }
+}
+
+fn file_name(name: &XorName) -> String {
+    name.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn name_from_file(file_name: &str) -> Option<XorName> {
+    if file_name.len() != XOR_NAME_LEN * 2 {
+        return None;
+    }
+    let mut name = [0_u8; XOR_NAME_LEN];
+    for (index, byte) in name.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(file_name.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+    Some(XorName(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::{env::temp_dir, time::Duration};
+    use tokio::time::sleep;
+
+    fn cache_dir() -> PathBuf {
+        temp_dir().join(format!(
+            "sn_client_chunk_cache_{}",
+            file_name(&XorName::random())
+        ))
+    }
+
+    #[tokio::test]
+    async fn chunk_cache_stores_and_removes_chunks() -> Result<()> {
+        let dir = cache_dir();
+        let cache = ChunkCache::open(&dir, 1024)?;
+        let name = XorName::random();
+
+        assert_eq!(cache.get(&name).await, None);
+        cache.put(name, b"chunk").await?;
+        assert!(cache.contains(&name));
+        assert_eq!(cache.get(&name).await, Some(b"chunk".to_vec()));
+        assert_eq!(cache.size(), 5);
+
+        cache.remove(&name).await;
+        assert_eq!(cache.get(&name).await, None);
+        assert_eq!(cache.size(), 0);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_cache_evicts_least_recently_used() -> Result<()> {
+        let dir = cache_dir();
+        let cache = ChunkCache::open(&dir, 10)?;
+        let (first, second, third) = (XorName::random(), XorName::random(), XorName::random());
+
+        cache.put(first, b"1111").await?;
+        cache.put(second, b"2222").await?;
+        // use the first one so the second one becomes the least recently used
+        assert!(cache.get(&first).await.is_some());
+        cache.put(third, b"3333").await?;
+
+        assert!(cache.get(&first).await.is_some());
+        assert_eq!(cache.get(&second).await, None);
+        assert!(cache.get(&third).await.is_some());
+        assert_eq!(cache.size(), 8);
+
+        // chunks bigger than the cache are not held
+        cache.put(XorName::random(), &[0; 11]).await?;
+        assert_eq!(cache.size(), 8);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_cache_is_kept_across_opens() -> Result<()> {
+        let dir = cache_dir();
+        let name = XorName::random();
+        {
+            let cache = ChunkCache::open(&dir, 1024)?;
+            cache.put(name, b"chunk").await?;
+        }
+
+        let cache = ChunkCache::open(&dir, 1024)?;
+        assert_eq!(cache.get(&name).await, Some(b"chunk".to_vec()));
+
+        // a smaller limit evicts what no longer fits
+        drop(cache);
+        let cache = ChunkCache::open(&dir, 4)?;
+        assert_eq!(cache.get(&name).await, None);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+    #[tokio::test]
+    async fn chunk_cache_keeps_the_order_of_use_across_opens() -> Result<()> {
+        let dir = cache_dir();
+        let (first, second) = (XorName::random(), XorName::random());
+        {
+            let cache = ChunkCache::open(&dir, 1024)?;
+            cache.put(first, b"1111").await?;
+            sleep(Duration::from_millis(20)).await;
+            cache.put(second, b"2222").await?;
+            sleep(Duration::from_millis(20)).await;
+            // reading the first one makes the second one the least recently used
+            assert!(cache.get(&first).await.is_some());
+        }
+
+        let cache = ChunkCache::open(&dir, 4)?;
+        assert!(cache.contains(&first));
+        assert!(!cache.contains(&second));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_cache_removes_stale_files_on_open() -> Result<()> {
+        let dir = cache_dir();
+        fs::create_dir_all(&dir)?;
+        let name = XorName::random();
+        let stale = dir.join(format!("{}.{:016x}.tmp", file_name(&name), 0));
+        fs::write(&stale, b"partial chunk")?;
+
+        let cache = ChunkCache::open(&dir, 1024)?;
+        assert!(!stale.exists());
+        assert_eq!(cache.size(), 0);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -7,34 +7,70 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::Client;
-use crate::errors::Error;
+use crate::{connections::CmdErrors, errors::Error, utils::sign_client_msg};
 use log::debug;
-use sn_data_types::{PublicKey, Signature};
-use sn_messaging::client::{ClientSigned, Cmd};
+use sn_messaging::{
+    client::{ClientSigned, Cmd},
+    MessageId,
+};
+use std::time::Duration;
 
 impl Client {
-    /// Send a Cmd to the network
+    /// Set how long a command waits for elders to report an error, before it's deemed accepted
+    /// by the network. Commands rejected within it return the error reported as
+    /// `Error::ErrorMessage`. It defaults to zero, sending commands without waiting, in which
+    /// case errors are only reported as `ClientEvent::CmdError` to `Client::subscribe` streams.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use std::time::Duration;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// // Give slow elders more time to reject commands
+    /// client.set_cmd_error_wait(Duration::from_secs(5));
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn set_cmd_error_wait(&mut self, wait: Duration) {
+        self.session.set_cmd_error_wait(wait);
+    }
+
+    /// Send a Cmd to the network, signed for the given message id
     pub(crate) async fn send_signed_command(
         &self,
         cmd: Cmd,
-        client_pk: PublicKey,
-        signature: Signature,
+        client_signed: ClientSigned,
+        msg_id: MessageId,
     ) -> Result<(), Error> {
         debug!("Sending Cmd: {:?}", cmd);
-        let client_signed = ClientSigned {
-            public_key: client_pk,
-            signature,
-        };
-
-        self.session.send_cmd(cmd, client_signed).await
+        self.session.send_cmd(cmd, client_signed, msg_id).await
     }
 
     // Send a Cmd to the network without awaiting for a response.
     // This function is a helper private to this module.
     pub(crate) async fn send_cmd(&self, cmd: Cmd) -> Result<(), Error> {
-        let client_pk = self.public_key();
-        let signature = self.keypair.sign(b"TODO");
+        let msg_id = MessageId::new();
+        let client_signed = sign_client_msg(&self.keypair, &cmd, &msg_id)?;
+
+        self.send_signed_command(cmd, client_signed, msg_id).await
+    }
+
+    // Send a Cmd to the network, leaving the errors elders may report for it to be awaited
+    // later with `await_cmd_errors`, e.g. while sending other messages.
+    pub(crate) async fn dispatch_cmd(&self, cmd: Cmd) -> Result<CmdErrors, Error> {
+        let msg_id = MessageId::new();
+        let client_signed = sign_client_msg(&self.keypair, &cmd, &msg_id)?;
+
+        debug!("Dispatching Cmd: {:?}", cmd);
+        self.session.dispatch_cmd(cmd, client_signed, msg_id).await
+    }
 
-        self.send_signed_command(cmd, client_pk, signature).await
+    // Await the errors reported for a Cmd sent with `dispatch_cmd`, as `send_cmd` does.
+    pub(crate) async fn await_cmd_errors(&self, reported_errors: CmdErrors) -> Result<(), Error> {
+        self.session.await_cmd_errors(reported_errors).await
     }
 }
@@ -0,0 +1,178 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+use futures::stream::{self, Stream};
+use log::warn;
+use sn_data_types::CreditAgreementProof;
+use sn_messaging::{
+    client::{CmdError, ProcessingError},
+    MessageId,
+};
+use std::collections::BTreeSet;
+use threshold_crypto::PublicKey as BlsPublicKey;
+use tokio::sync::broadcast::error::RecvError;
+use xor_name::{Prefix, XorName};
+
+/// Something which happened to the client, as reported by `Client::subscribe`.
+#[derive(Clone, Debug)]
+pub enum ClientEvent {
+    /// Elders reported an error for a message sent by the client.
+    CmdError {
+        /// The error reported.
+        error: CmdError,
+        /// Id of the message the error relates to.
+        msg_id: MessageId,
+    },
+    /// Elders reported they couldn't process a message sent by the client.
+    ProcessingError(ProcessingError),
+    /// The section the client talks to has new elders, or a new key.
+    SectionUpdated {
+        /// Prefix of the section.
+        prefix: Prefix,
+        /// Public key of the section.
+        section_key: BlsPublicKey,
+        /// Names of the elders of the section.
+        elders: BTreeSet<XorName>,
+    },
+    /// The client bootstrapped again, after the elders it was connected to redirected it or
+    /// rejected its bootstrap, or it reconnected to an elder it lost the connection to.
+    Reconnected,
+    /// A transfer to the client's key was found while syncing its transfer history, which
+    /// happens when querying its balance among others. The transfers received before the
+    /// history was first synced aren't notified.
+    TransferReceived(CreditAgreementProof),
+}
+
+impl Client {
+    /// Subscribe to the events of the client, such as errors reported by elders, changes of
+    /// the section and transfers received.
+    ///
+    /// Only the events which happen after subscribing are returned. Should a subscriber fall
+    /// too far behind, the oldest events it missed are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use futures::StreamExt;
+    /// use sn_client::{client::ClientEvent, Client};
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let mut events = client.subscribe();
+    /// while let Some(event) = events.next().await {
+    ///     if let ClientEvent::TransferReceived(credit) = event {
+    ///         println!("Received {}", credit.signed_credit.credit.amount);
+    ///     }
+    /// }
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn subscribe(&self) -> impl Stream<Item = ClientEvent> + Unpin {
+        let receiver = self.session.subscribe();
+        Box::pin(stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Event subscriber fell behind, {} events skipped", skipped)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "mock-network")]
+    use crate::utils::test_utils::MockNetwork;
+    use crate::utils::test_utils::{create_test_client, create_test_client_with};
+    use anyhow::{bail, Result};
+    use futures::StreamExt;
+    use rand::rngs::OsRng;
+    use sn_data_types::{Keypair, Token};
+    use std::{str::FromStr, time::Duration};
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    pub async fn transfers_received_are_notified() -> Result<()> {
+        let sender = create_test_client().await?;
+        let receiver = create_test_client().await?;
+        let mut events = receiver.subscribe();
+
+        let _ = sender
+            .send_tokens(receiver.public_key(), Token::from_str("1")?)
+            .await?;
+        let _ = receiver.get_balance().await?;
+
+        match timeout(Duration::from_secs(5), events.next()).await {
+            Ok(Some(ClientEvent::TransferReceived(credit))) => {
+                assert_eq!(credit.signed_credit.credit.amount, Token::from_str("1")?)
+            }
+            other => bail!("Unexpected event: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn transfers_received_before_first_sync_are_not_notified() -> Result<()> {
+        let sender = create_test_client().await?;
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let _ = sender
+            .send_tokens(keypair.public_key(), Token::from_str("1")?)
+            .await?;
+
+        let receiver = create_test_client_with(Some(keypair)).await?;
+        let mut events = receiver.subscribe();
+        let _ = receiver.get_balance().await?;
+        let _ = receiver.get_balance().await?;
+
+        while let Ok(Some(event)) = timeout(Duration::from_secs(1), events.next()).await {
+            if let ClientEvent::TransferReceived(credit) = event {
+                bail!(
+                    "Transfer received before syncing was notified: {:?}",
+                    credit
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[tokio::test]
+    pub async fn reconnections_to_elders_are_notified() -> Result<()> {
+        let client = create_test_client().await?;
+        let network = MockNetwork::shared().await?;
+        let mut events = client.subscribe();
+
+        network.drop_connections();
+        let reconnected = timeout(Duration::from_secs(10), async {
+            while let Some(event) = events.next().await {
+                if let ClientEvent::Reconnected = event {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+        if !matches!(reconnected, Ok(true)) {
+            bail!("Reconnection wasn't notified");
+        }
+
+        // the client still talks to the section
+        let _ = client.get_balance().await?;
+
+        Ok(())
+    }
+}
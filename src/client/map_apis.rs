@@ -70,6 +70,8 @@ impl Client {
         entries: Option<MapSeqEntries>,
         permissions: Option<BTreeMap<PublicKey, MapPermissionSet>>,
     ) -> Result<MapAddress, Error> {
+        self.check_writable()?;
+
         let data = Map::Seq(SeqMap::new_with_data(
             name,
             tag,
@@ -124,6 +126,8 @@ impl Client {
         entries: Option<MapUnseqEntries>,
         permissions: Option<BTreeMap<PublicKey, MapPermissionSet>>,
     ) -> Result<MapAddress, Error> {
+        self.check_writable()?;
+
         let data = Map::Unseq(UnseqMap::new_with_data(
             name,
             tag,
@@ -174,6 +178,8 @@ impl Client {
     /// # Ok(()) } ); }
     /// ```
     pub async fn delete_map(&self, address: MapAddress) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Map(MapWrite::Delete(address));
 
         self.pay_and_send_data_command(cmd).await
@@ -186,6 +192,8 @@ impl Client {
         user: PublicKey,
         version: u64,
     ) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Map(MapWrite::DelUserPermissions {
             address,
             user,
@@ -203,6 +211,8 @@ impl Client {
         permissions: MapPermissionSet,
         version: u64,
     ) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Map(MapWrite::SetUserPermissions {
             address,
             user,
@@ -219,6 +229,8 @@ impl Client {
         address: MapAddress,
         changes: MapEntryActions,
     ) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Map(MapWrite::Edit { address, changes });
 
         self.pay_and_send_data_command(cmd).await
@@ -861,13 +873,13 @@ mod tests {
         let _ = retry_loop!(client.store_unseq_map(name, tag, owner, None, None));
 
         let mut client = create_test_client().await?;
+        client.set_cmd_error_wait(Duration::from_secs(2));
 
-        client.delete_map(mapref).await?;
-
-        match client.expect_cmd_error().await {
-            Some(sn_messaging::client::CmdError::Data(
-                sn_messaging::client::Error::AccessDenied(_),
-            )) => Ok(()),
+        match client.delete_map(mapref).await {
+            Err(Error::ErrorMessage {
+                source: ErrorMessage::AccessDenied(_),
+                ..
+            }) => Ok(()),
             _ => bail!("Unexpected: Deletion by non-owners should fail"),
         }
     }
@@ -7,42 +7,67 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod blob_apis;
+mod blob_reader;
 mod blob_storage;
+mod builder;
+mod chunk_cache;
 mod commands;
+mod events;
 mod map_apis;
+mod outbox;
 mod queries;
 mod register_apis;
+mod replicas;
 mod sequence_apis;
 mod transfer_actor;
+mod watch;
 
+pub use self::blob_reader::BlobReader;
+pub use self::builder::ClientBuilder;
+pub use self::chunk_cache::ChunkCache;
+pub use self::events::ClientEvent;
+pub use self::replicas::ReplicaStore;
 // sn_transfers wrapper
-pub use self::transfer_actor::SafeTransferActor;
+pub use self::transfer_actor::{SafeTransferActor, TransferHistory, TransferRecord};
 
-use crate::{config_handler::Config, connections::Session, errors::Error};
+use crate::{
+    config_handler::ClientConfig,
+    connections::{transport::Transport, Session},
+    errors::Error,
+};
 use crdts::Dot;
 use log::{debug, info, trace, warn};
 use rand::rngs::OsRng;
-use sn_data_types::{Keypair, PublicKey, SectionElders, Token};
+use sn_data_types::{CreditId, Keypair, PublicKey, SectionElders, Token};
 use sn_messaging::client::{Cmd, CmdError, DataCmd};
 use std::{
+    io,
     path::Path,
     str::FromStr,
     {collections::HashSet, net::SocketAddr, sync::Arc},
 };
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::RwLock;
+use tokio::task;
+use tokio::time::sleep;
 
-// Number of attempts to make when trying to bootstrap to the network
-const NUM_OF_BOOTSTRAPPING_ATTEMPTS: u8 = 1;
+// Number of chunks stored or fetched concurrently, unless set otherwise
+const DEFAULT_CHUNK_CONCURRENCY: usize = 10;
 
 /// Client object
 #[derive(Clone)]
 pub struct Client {
     keypair: Keypair,
-    transfer_actor: Arc<RwLock<SafeTransferActor<Keypair>>>,
+    // The transfer actor of the client, which read-only clients don't have
+    transfer_actor: Option<Arc<RwLock<SafeTransferActor<Keypair>>>>,
     simulated_farming_payout_dot: Dot<PublicKey>,
     incoming_errors: Arc<RwLock<Receiver<CmdError>>>,
     session: Session,
+    chunk_concurrency: usize,
+    chunk_cache: Option<Arc<ChunkCache>>,
+    replicas: Arc<ReplicaStore>,
+    // Credits already known of, once the transfer history has first been synced
+    received_credits: Arc<RwLock<Option<HashSet<CreditId>>>>,
 }
 
 /// Easily manage connections to/from The Safe Network with the client and its APIs.
@@ -55,6 +80,8 @@ impl Client {
     /// is passed, a random keypair will be used, which provides a client that can only perform Read operations (at
     /// least until the client's SecretKey receives some token).
     ///
+    /// See `ClientBuilder` for more settings.
+    ///
     /// # Examples
     ///
     /// Create a random client
@@ -75,6 +102,68 @@ impl Client {
         optional_keypair: Option<Keypair>,
         config_file_path: Option<&Path>,
         bootstrap_config: Option<HashSet<SocketAddr>>,
+    ) -> Result<Self, Error> {
+        let mut builder = ClientBuilder::new();
+        if let Some(keypair) = optional_keypair {
+            builder = builder.keypair(keypair);
+        }
+        if let Some(path) = config_file_path {
+            builder = builder.config_file_path(path);
+        }
+        if let Some(contacts) = bootstrap_config {
+            builder = builder.bootstrap_contacts(contacts);
+        }
+
+        builder.build().await
+    }
+
+    /// Create a Safe Network client instance which talks to the network over the given transport,
+    /// rather than over qp2p as set up by `Client::new`. Keypair handling is the same as for
+    /// `Client::new`. The timeouts and retry policy are those of the given `ClientConfig`, or
+    /// those of the default config file if none is given.
+    ///
+    /// # Examples
+    ///
+    /// Create a random client over a qp2p transport with a custom configuration
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::{transport::QuicP2pTransport, Client, QuicP2pConfig};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = read_network_conn_info()?;
+    /// let transport = QuicP2pTransport::new(QuicP2pConfig {
+    ///     hard_coded_contacts: bootstrap_contacts,
+    ///     ..Default::default()
+    /// })?;
+    /// let client = Client::with_transport(None, Arc::new(transport), None).await?;
+    /// let _some_balance = client.get_balance().await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn with_transport(
+        optional_keypair: Option<Keypair>,
+        transport: Arc<dyn Transport>,
+        config: Option<ClientConfig>,
+    ) -> Result<Self, Error> {
+        let mut builder = ClientBuilder::new().transport(transport);
+        if let Some(keypair) = optional_keypair {
+            builder = builder.keypair(keypair);
+        }
+        if let Some(config) = config {
+            builder = builder.client_config(config);
+        }
+
+        builder.build().await
+    }
+
+    // Bootstrap a client to the network over the given transport
+    async fn connect(
+        optional_keypair: Option<Keypair>,
+        transport: Arc<dyn Transport>,
+        config: ClientConfig,
+        simulated_payout: bool,
+        read_only: bool,
     ) -> Result<Self, Error> {
         let mut rng = OsRng;
 
@@ -93,15 +182,11 @@ impl Client {
             }
         };
 
-        let mut qp2p_config = Config::new(config_file_path, bootstrap_config).qp2p;
-        // We use feature `no-igd` so this will use the echo service only
-        qp2p_config.forward_port = true;
-
         // Incoming error notifiers
         let (err_sender, err_receiver) = tokio::sync::mpsc::channel::<CmdError>(10);
 
         // Create the session with the network
-        let mut session = Session::new(qp2p_config, err_sender)?;
+        let mut session = Session::new(transport, err_sender, config);
         let client_pk = keypair.public_key();
 
         // Bootstrap to the network, connecting to the section responsible
@@ -115,40 +200,56 @@ impl Client {
 
         let simulated_farming_payout_dot = Dot::new(random_payment_pk, 0);
 
-        let elder_pk_set = session
-            .section_key_set
-            .read()
-            .await
-            .clone()
-            .ok_or(Error::NotBootstrapped)?;
-        let elder_names = session.get_elder_names().await;
-        let elders = SectionElders {
-            prefix: session
-                .section_prefix()
+        let transfer_actor = if read_only {
+            debug!("No transfer actor is set up for read-only clients");
+            None
+        } else {
+            let elder_pk_set = session
+                .section_key_set
+                .read()
                 .await
-                .ok_or(Error::NoSectionPrefixKnown)?,
-            names: elder_names,
-            key_set: elder_pk_set,
+                .clone()
+                .ok_or(Error::NotBootstrapped)?;
+            let elder_names = session.get_elder_names().await;
+            let elders = SectionElders {
+                prefix: session
+                    .section_prefix()
+                    .await
+                    .ok_or(Error::NoSectionPrefixKnown)?,
+                names: elder_names,
+                key_set: elder_pk_set,
+            };
+
+            Some(Arc::new(RwLock::new(SafeTransferActor::new(
+                keypair.clone(),
+                elders,
+            ))))
         };
 
-        let transfer_actor = Arc::new(RwLock::new(SafeTransferActor::new(keypair.clone(), elders)));
-
         let mut client = Self {
             keypair,
             transfer_actor,
             simulated_farming_payout_dot,
             session,
             incoming_errors: Arc::new(RwLock::new(err_receiver)),
+            chunk_concurrency: DEFAULT_CHUNK_CONCURRENCY,
+            chunk_cache: None,
+            replicas: Arc::new(ReplicaStore::new()),
+            received_credits: Arc::new(RwLock::new(None)),
         };
 
         if cfg!(feature = "simulated-payouts") {
             // only trigger simulated payouts on new _random_ clients
-            if is_random_client {
+            if read_only {
+                debug!("No simulated payout occurs for read-only clients")
+            } else if is_random_client && simulated_payout {
                 debug!("Attempting to trigger simulated payout");
                 // we're testing, and currently a lot of tests expect 10 token to start
                 let _ = client
                     .trigger_simulated_farming_payout(Token::from_str("10")?)
                     .await?;
+            } else if is_random_client {
+                debug!("Simulated payout disabled for this client")
             } else {
                 warn!("No automatic simulated payout occurs for clients created for pre-existing SecretKeys")
             }
@@ -196,11 +297,57 @@ impl Client {
         self.keypair().public_key()
     }
 
+    /// Whether the client is read-only, i.e. it was built with `ClientBuilder::read_only`, in
+    /// which case all writes fail with `Error::ReadOnlyClient`.
+    pub fn is_read_only(&self) -> bool {
+        self.transfer_actor.is_none()
+    }
+
+    // Reject writes before any work is done for them, if the client is read-only
+    fn check_writable(&self) -> Result<(), Error> {
+        self.transfer_actor().map(|_| ())
+    }
+
+    // The transfer actor of the client, failing for read-only clients which have none
+    fn transfer_actor(&self) -> Result<&RwLock<SafeTransferActor<Keypair>>, Error> {
+        self.transfer_actor.as_deref().ok_or(Error::ReadOnlyClient)
+    }
+
+    /// Set the store holding the local replicas of Sequences and Registers, which by default
+    /// are only held in memory. Using a store opened from a directory keeps the replicas, and
+    /// the writes not sent to the network yet, across runs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::{client::ReplicaStore, Client};
+    /// use std::path::Path;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// client.set_replica_store(ReplicaStore::open(Path::new("/tmp/replicas"))?);
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn set_replica_store(&mut self, mut store: ReplicaStore) {
+        store.load_actor(&self.public_key());
+        self.replicas = Arc::new(store);
+    }
+
     // Private helper to obtain payment proof for a data command, send it to the network,
     // and also apply the payment to local replica actor.
     async fn pay_and_send_data_command(&self, cmd: DataCmd) -> Result<(), Error> {
+        self.get_history().await?;
+
+        self.pay_and_send_synced_data_command(cmd).await
+    }
+
+    // Same as `pay_and_send_data_command`, the transfer history of the local actor being
+    // expected to be in sync already, as it is after a previous payment was applied to it.
+    async fn pay_and_send_synced_data_command(&self, cmd: DataCmd) -> Result<(), Error> {
         // Payment for PUT
-        let payment_proof = self.create_write_payment_proof(&cmd).await?;
+        let payment_proof = self.pay_for_write(&cmd).await?;
 
         // The _actual_ message
         let cmd = Cmd::Data {
@@ -213,14 +360,72 @@ impl Client {
         self.apply_write_payment_to_local_actor(payment_proof).await
     }
 
+    /// Pay for several data commands, and send them to the network one after the other.
+    ///
+    /// The section registers a payment along with the command it pays for, so each command is
+    /// paid for with a transfer of its own, which can only be made once the previous one is
+    /// registered. The transfer history is synced with the network once for all of them,
+    /// rather than once per command, and the commands are sent in the order given, stopping
+    /// at the first failure.
+    ///
+    /// # Examples
+    ///
+    /// Store several public Blobs
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::{Blob, PublicBlob};
+    /// use sn_messaging::client::{BlobWrite, DataCmd};
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let cmds = vec![b"first".to_vec(), b"second".to_vec()]
+    ///     .into_iter()
+    ///     .map(|value| DataCmd::Blob(BlobWrite::New(Blob::Public(PublicBlob::new(value)))))
+    ///     .collect();
+    /// client.pay_and_send_data_commands(cmds).await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn pay_and_send_data_commands(&self, cmds: Vec<DataCmd>) -> Result<(), Error> {
+        self.check_writable()?;
+
+        if cmds.is_empty() {
+            return Ok(());
+        }
+        debug!("Paying for and sending {} data commands", cmds.len());
+        self.get_history().await?;
+
+        for cmd in cmds {
+            self.pay_and_send_synced_data_command(cmd).await?;
+        }
+
+        Ok(())
+    }
+
     #[cfg(test)]
     pub async fn expect_cmd_error(&mut self) -> Option<CmdError> {
         self.incoming_errors.write().await.recv().await
     }
 }
 
-/// Utility function that bootstraps a client to the network. If there is a failure then it retries.
-/// After a maximum of three attempts if the boostrap process still fails, then an error is returned.
+// Run file system operations on the thread pool dedicated to blocking tasks, so as not to
+// block the async runtime
+async fn blocking<T, F>(operation: F) -> Result<T, Error>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let result = task::spawn_blocking(operation)
+        .await
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+    Ok(result?)
+}
+
+/// Utility function that bootstraps a client to the network. If there is a failure then it retries,
+/// backing off as configured. After the configured number of attempts if the boostrap process
+/// still fails, then an error is returned.
 async fn attempt_bootstrap(session: &mut Session, client_pk: PublicKey) -> Result<(), Error> {
     let mut attempts: u8 = 0;
     loop {
@@ -229,12 +434,13 @@ async fn attempt_bootstrap(session: &mut Session, client_pk: PublicKey) -> Resul
             Ok(()) => return Ok(()),
             Err(err) => {
                 attempts += 1;
-                if attempts < NUM_OF_BOOTSTRAPPING_ATTEMPTS {
+                if attempts < session.config().bootstrap_attempts {
                     trace!(
                         "Error connecting to network! {:?}\nRetrying... ({})",
                         err,
                         attempts
                     );
+                    sleep(session.config().retry_delay(u32::from(attempts) - 1)).await;
                 } else {
                     return Err(err);
                 }
@@ -246,8 +452,14 @@ async fn attempt_bootstrap(session: &mut Session, client_pk: PublicKey) -> Resul
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::test_utils::{create_test_client, create_test_client_with};
+    use crate::retry_loop;
+    use crate::utils::{
+        generate_random_vector,
+        test_utils::{create_test_client, create_test_client_with},
+    };
     use anyhow::Result;
+    use sn_data_types::{Blob, PublicBlob};
+    use sn_messaging::client::BlobWrite;
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
     #[tokio::test]
@@ -282,6 +494,33 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    pub async fn several_data_commands_paid_for_one_by_one() -> Result<()> {
+        let client = create_test_client().await?;
+        let blobs: Vec<Blob> = (0..3)
+            .map(|_| Blob::Public(PublicBlob::new(generate_random_vector::<u8>(10))))
+            .collect();
+        let cmds = blobs
+            .iter()
+            .map(|blob| DataCmd::Blob(BlobWrite::New(blob.clone())))
+            .collect();
+
+        let balance_before = client.get_local_balance().await;
+        client.pay_and_send_data_commands(cmds).await?;
+        assert_ne!(client.get_local_balance().await, balance_before);
+
+        // each of them was paid for with a transfer of its own
+        let history = client.get_transfer_history(client.public_key(), 0).await?;
+        assert_eq!(history.debits.len(), 3);
+
+        for blob in blobs {
+            let fetched = retry_loop!(client.fetch_blob_from_network(*blob.address()));
+            assert_eq!(fetched, blob);
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     pub async fn long_lived_connection_survives() -> Result<()> {
         let client = create_test_client().await?;
@@ -0,0 +1,220 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+use crate::Error;
+use log::{debug, trace};
+use sn_data_types::{
+    register::{Address as RegisterAddress, Entry, RegisterOp},
+    SequenceAddress, SequenceEntry, SequenceOp,
+};
+use sn_messaging::client::{DataCmd, RegisterWrite, SequenceWrite};
+
+// A write to the local replica of some data, waiting to be sent to the network
+enum OutboxOp {
+    Sequence(SequenceAddress, SequenceOp<SequenceEntry>),
+    Register(RegisterAddress, RegisterOp<Entry>),
+}
+
+impl OutboxOp {
+    fn cmd(&self) -> DataCmd {
+        match self {
+            Self::Sequence(_, op) => DataCmd::Sequence(SequenceWrite::Edit(op.clone())),
+            Self::Register(_, op) => DataCmd::Register(RegisterWrite::Edit(op.clone())),
+        }
+    }
+}
+
+impl Client {
+    /// Pay for and send to the network the writes queued in the local replicas of Sequences
+    /// and Registers, returning the number of writes sent.
+    ///
+    /// Writes are queued with `queue_sequence_append` and `queue_register_write`, or when
+    /// sending them failed with `append_to_sequence` and `write_to_register`. The data held by
+    /// the network is merged in first, so concurrent writes made by others are resolved as the
+    /// CRDTs do, and the writes the network already holds aren't sent again. The writes left
+    /// are then paid for with a transfer each, and sent one after the other, those to a single
+    /// replica in the order they were made.
+    ///
+    /// The outbox is only durable if the client's replica store was opened from a directory,
+    /// see `set_replica_store`. The default store holds it in memory only, so the writes
+    /// queued are lost if the client is dropped, or the process exits, before they're sent.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::{client::ReplicaStore, Client};
+    /// use std::path::Path;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// // Writes queued by a previous run are picked up from the store
+    /// client.set_replica_store(ReplicaStore::open(Path::new("/tmp/replicas"))?);
+    /// let sent = client.flush_outbox().await?;
+    /// println!("{} queued writes sent", sent);
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn flush_outbox(&self) -> Result<usize, Error> {
+        self.check_writable()?;
+
+        // The writes to send, grouped by replica
+        let mut outbox: Vec<Vec<OutboxOp>> = Vec::new();
+
+        // Merge the network's data in before collecting the writes to send
+        for address in self.replicas.sequences.with_unsent() {
+            let _ = self.fetch_and_merge_sequence(address).await?;
+            outbox.push(
+                self.replicas
+                    .sequences
+                    .unsent(&address)
+                    .into_iter()
+                    .map(|op| OutboxOp::Sequence(address, op))
+                    .collect(),
+            );
+        }
+        for address in self.replicas.registers.with_unsent() {
+            let _ = self.fetch_and_merge_register(address).await?;
+            outbox.push(
+                self.replicas
+                    .registers
+                    .unsent(&address)
+                    .into_iter()
+                    .map(|op| OutboxOp::Register(address, op))
+                    .collect(),
+            );
+        }
+        outbox.retain(|ops| !ops.is_empty());
+
+        if outbox.is_empty() {
+            trace!("Outbox is empty, nothing to flush");
+            return Ok(0);
+        }
+        debug!(
+            "Flushing {} write(s) to {} replica(s) from the outbox",
+            outbox.iter().map(Vec::len).sum::<usize>(),
+            outbox.len()
+        );
+
+        self.pay_and_send_outbox(outbox).await
+    }
+
+    // Pay for the queued writes and send them, one at a time, as each payment can only be made
+    // once the previous one is registered. The writes to a single replica are sent in the
+    // order they were made, as the CRDT ops made by an actor have to reach the network in
+    // that order. Stops at the first failure, returning the number of writes sent so far.
+    async fn pay_and_send_outbox(&self, outbox: Vec<Vec<OutboxOp>>) -> Result<usize, Error> {
+        self.get_history().await?;
+
+        let mut sent = 0;
+        for op in outbox.into_iter().flatten() {
+            self.pay_and_send_synced_data_command(op.cmd()).await?;
+
+            match &op {
+                OutboxOp::Sequence(address, op) => {
+                    self.replicas.sequences.mark_sent(address, op).await
+                }
+                OutboxOp::Register(address, op) => {
+                    self.replicas.registers.mark_sent(address, op).await
+                }
+            }
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{retry_loop, retry_loop_for_pattern, utils::test_utils::create_test_client};
+    use anyhow::Result;
+    use sn_data_types::{SequencePublicPermissions, SequenceUser};
+    use std::collections::BTreeMap;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    pub async fn queued_sequence_appends_are_flushed() -> Result<()> {
+        let client = create_test_client().await?;
+        let owner = client.public_key();
+        let mut perms = BTreeMap::<SequenceUser, SequencePublicPermissions>::new();
+        let _ = perms.insert(
+            SequenceUser::Key(owner),
+            SequencePublicPermissions::new(true),
+        );
+        let address = client
+            .store_public_sequence(None, XorName::random(), 10, owner, perms)
+            .await?;
+        let _ = retry_loop!(client.get_sequence(address));
+
+        client
+            .queue_sequence_append(address, b"VALUE1".to_vec())
+            .await?;
+        client
+            .queue_sequence_append(address, b"VALUE2".to_vec())
+            .await?;
+        let local = client.get_local_sequence(address);
+        assert_eq!(local.map(|seq| seq.len(None)).transpose()?, Some(2));
+
+        let balance_before = client.get_local_balance().await;
+        assert_eq!(client.flush_outbox().await?, 2);
+        assert_ne!(client.get_local_balance().await, balance_before);
+
+        // nothing is left to send
+        assert_eq!(client.flush_outbox().await?, 0);
+
+        // the appends are held by the network, as seen by another client without them locally
+        let other_client = create_test_client().await?;
+        let _ = retry_loop_for_pattern!(other_client.get_sequence_entry(address, 1), Ok(entry) if entry == b"VALUE2");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn queued_writes_to_several_replicas_are_flushed() -> Result<()> {
+        let client = create_test_client().await?;
+        let owner = client.public_key();
+        let mut perms = BTreeMap::<SequenceUser, SequencePublicPermissions>::new();
+        let _ = perms.insert(
+            SequenceUser::Key(owner),
+            SequencePublicPermissions::new(true),
+        );
+        let mut addresses = Vec::new();
+        for _ in 0..3 {
+            let address = client
+                .store_public_sequence(None, XorName::random(), 10, owner, perms.clone())
+                .await?;
+            let _ = retry_loop!(client.get_sequence(address));
+            addresses.push(address);
+        }
+        let debits_before = client.get_transfer_history(owner, 0).await?.debits.len();
+
+        for address in &addresses {
+            for value in &[b"VALUE1", b"VALUE2"] {
+                client
+                    .queue_sequence_append(*address, value.to_vec())
+                    .await?;
+            }
+        }
+        assert_eq!(client.flush_outbox().await?, 6);
+
+        // each of the writes was paid for on its own
+        let debits_after = client.get_transfer_history(owner, 0).await?.debits.len();
+        assert_eq!(debits_after, debits_before + 6);
+
+        // the writes to each replica reached the network in order
+        let other_client = create_test_client().await?;
+        for address in addresses {
+            let _ = retry_loop_for_pattern!(other_client.get_sequence_entry(address, 1), Ok(entry) if entry == b"VALUE2");
+            let first = other_client.get_sequence_entry(address, 0).await?;
+            assert_eq!(first, b"VALUE1");
+        }
+
+        Ok(())
+    }
+}
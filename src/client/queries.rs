@@ -7,34 +7,58 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::Client;
-use crate::{connections::QueryResult, errors::Error};
+use crate::{connections::QueryResult, errors::Error, utils::sign_client_msg};
 use log::debug;
-use sn_data_types::{PublicKey, Signature};
-use sn_messaging::client::{ClientSigned, Query};
+use sn_messaging::{
+    client::{ClientSigned, Query},
+    MessageId,
+};
 
 impl Client {
-    /// Send a Query to the network and await a response
+    /// Set how many of the elders a query is sent to must return matching responses before a
+    /// response is accepted. It defaults to 1, i.e. the first non-error response is taken, and
+    /// is capped to the number of elders queried.
+    ///
+    /// This applies to all but Blob queries, whose responses are verified against the address
+    /// of the Blob instead. A transfer history whose proofs are all signed by the section is
+    /// accepted from a single elder, as none of them could make it up. Should the elders
+    /// disagree, `Error::ConflictingQueryResponses` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let mut client = Client::new(None, None, bootstrap_contacts).await?;
+    /// // Only accept balances two elders agree on
+    /// client.set_query_response_threshold(2);
+    /// let _some_balance = client.get_balance().await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn set_query_response_threshold(&mut self, threshold: usize) {
+        self.session.set_query_response_threshold(threshold);
+    }
+
+    /// Send a Query to the network, signed for the given message id, and await a response
     pub(crate) async fn send_signed_query(
         &self,
         query: Query,
-        client_pk: PublicKey,
-        signature: Signature,
+        client_signed: ClientSigned,
+        msg_id: MessageId,
     ) -> Result<QueryResult, Error> {
         debug!("Sending Query: {:?}", query);
-        let client_signed = ClientSigned {
-            public_key: client_pk,
-            signature,
-        };
-
-        self.session.send_query(query, client_signed).await
+        self.session.send_query(query, client_signed, msg_id).await
     }
 
     // Send a Query to the network and await a response.
     // This function is a helper private to this module.
     pub(crate) async fn send_query(&self, query: Query) -> Result<QueryResult, Error> {
-        let client_pk = self.public_key();
-        let signature = self.keypair.sign(b"TODO");
+        let msg_id = MessageId::new();
+        let client_signed = sign_client_msg(&self.keypair, &query, &msg_id)?;
 
-        self.send_signed_query(query, client_pk, signature).await
+        self.send_signed_query(query, client_signed, msg_id).await
     }
 }
@@ -12,7 +12,7 @@ use log::{debug, trace};
 use sn_data_types::{
     register::{
         Address, Entry, EntryHash, Permissions, Policy, PrivatePermissions, PrivatePolicy,
-        PublicPermissions, PublicPolicy, Register, User,
+        PublicPermissions, PublicPolicy, Register, RegisterOp, User,
     },
     PublicKey,
 };
@@ -41,6 +41,8 @@ impl Client {
         owner: PublicKey,
         permissions: BTreeMap<PublicKey, PrivatePermissions>,
     ) -> Result<Address, Error> {
+        self.check_writable()?;
+
         trace!("Store Private Register data {:?}", name);
         let pk = self.public_key();
         let policy = PrivatePolicy { owner, permissions };
@@ -67,6 +69,8 @@ impl Client {
         owner: PublicKey,
         permissions: BTreeMap<User, PublicPermissions>,
     ) -> Result<Address, Error> {
+        self.check_writable()?;
+
         trace!("Store Public Register data {:?}", name);
         let pk = self.public_key();
         let policy = PublicPolicy { owner, permissions };
@@ -82,6 +86,8 @@ impl Client {
     ///
     /// You're only able to delete a PrivateRegister. Public data can no be removed from the network.
     pub async fn delete_register(&self, address: Address) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Register(RegisterWrite::Delete(address));
         // Payment for PUT
         let payment_proof = self.create_write_payment_proof(&cmd).await?;
@@ -93,6 +99,7 @@ impl Client {
         };
 
         self.send_cmd(cmd).await?;
+        self.replicas.registers.remove(&address).await;
 
         self.apply_write_payment_to_local_actor(payment_proof).await
     }
@@ -107,24 +114,105 @@ impl Client {
         entry: Entry,
         parents: BTreeSet<EntryHash>,
     ) -> Result<EntryHash, Error> {
-        // First we fetch it so we can get the causality info,
-        // either from local CRDT replica or from the network if not found
-        let mut register = self.get_register(address).await?;
+        self.check_writable()?;
 
-        // We can now write the entry to the Register
-        let (hash, mut op) = register.write(entry, parents)?;
-        let bytes = bincode::serialize(&op.crdt_op)?;
-        let signature = self.keypair.sign(&bytes);
-        op.signature = Some(signature);
+        let (hash, op) = self
+            .write_to_local_register(address, entry, parents)
+            .await?;
 
         // Finally we can send the mutation to the network's replicas
-        let cmd = DataCmd::Register(RegisterWrite::Edit(op));
+        self.send_register_op(address, op).await?;
 
-        self.pay_and_send_data_command(cmd).await?;
+        Ok(hash)
+    }
+
+    /// Write to the local replica of a Register only, queueing the write in the outbox
+    /// until `flush_outbox` is called, e.g. once the network is reachable again.
+    ///
+    /// The Register is fetched from the network if there is no local replica of it yet.
+    /// Writes made concurrently by others are merged in once the outbox is flushed.
+    /// Queued writes are lost when the client is dropped, unless its replica store was
+    /// opened from a directory, see `set_replica_store`.
+    pub async fn queue_register_write(
+        &self,
+        address: Address,
+        entry: Entry,
+        parents: BTreeSet<EntryHash>,
+    ) -> Result<EntryHash, Error> {
+        self.check_writable()?;
 
+        let (hash, _) = self
+            .write_to_local_register(address, entry, parents)
+            .await?;
         Ok(hash)
     }
 
+    // Write to the local replica, returning the signed op to send to the network
+    async fn write_to_local_register(
+        &self,
+        address: Address,
+        entry: Entry,
+        parents: BTreeSet<EntryHash>,
+    ) -> Result<(EntryHash, RegisterOp<Entry>), Error> {
+        // First we need the causality info, either from the local CRDT replica
+        // or from the network if we don't have a replica yet
+        if !self.replicas.registers.contains(&address) {
+            let _ = self.fetch_and_merge_register(address).await?;
+        }
+
+        // We can now write the entry to the local replica
+        self.replicas
+            .registers
+            .write(&address, |register| {
+                let (hash, mut op) = register.write(entry, parents)?;
+                let bytes = bincode::serialize(&op.crdt_op)?;
+                let signature = self.keypair.sign(&bytes);
+                op.signature = Some(signature);
+                register.apply_op(op.clone())?;
+                Ok((hash, op))
+            })
+            .await
+    }
+
+    /// Send to the network the writes made to the local replica of a Register which couldn't
+    /// be sent at the time, e.g. while the network was unreachable, merging in the Register
+    /// held by the network first.
+    pub async fn sync_register(&self, address: Address) -> Result<(), Error> {
+        self.check_writable()?;
+
+        let _ = self.fetch_and_merge_register(address).await?;
+        let ops = self.replicas.registers.unsent(&address);
+        debug!("Sending {} pending write(s) to {:?}", ops.len(), address);
+        for op in ops {
+            self.send_register_op(address, op).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the local replica of a Register, including the writes not yet sent to the network,
+    /// without fetching it from the network. This is `None` if this client never wrote to,
+    /// nor synced, the Register.
+    pub fn get_local_register(&self, address: Address) -> Option<Register> {
+        self.replicas.registers.get(&address)
+    }
+
+    // Fetch the Register from the network and merge it into the local replica, which may hold
+    // writes the network doesn't have yet, returning the result
+    pub(crate) async fn fetch_and_merge_register(
+        &self,
+        address: Address,
+    ) -> Result<Register, Error> {
+        let register = self.get_register(address).await?;
+        Ok(self.replicas.registers.merge(address, register).await)
+    }
+
+    async fn send_register_op(&self, address: Address, op: RegisterOp<Entry>) -> Result<(), Error> {
+        let cmd = DataCmd::Register(RegisterWrite::Edit(op.clone()));
+        self.pay_and_send_data_command(cmd).await?;
+        self.replicas.registers.mark_sent(&address, &op).await;
+        Ok(())
+    }
+
     /// Store a new Register data object
     /// Wraps msg_contents for payment validation and mutation
     pub(crate) async fn pay_and_write_register_to_network(
@@ -0,0 +1,542 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::blocking;
+use crate::Error;
+use log::{debug, trace, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sn_data_types::{
+    register::{Address as RegisterAddress, Entry, Register, RegisterOp},
+    PublicKey, Sequence, SequenceAddress, SequenceEntry, SequenceOp,
+};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs,
+    hash::Hash,
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+use tokio::sync::Mutex as AsyncMutex;
+
+/// Local replicas of the Sequences and Registers the client has synced or written to.
+///
+/// Writes are applied to the local replica first, and the operations not sent to the network
+/// yet are kept, so they can be sent later and re-applied on top of the state fetched from the
+/// network. When opened from a directory, replicas are kept across runs, apart for each client
+/// keypair, so that clients sharing a directory don't pick up each other's writes.
+#[derive(Default)]
+pub struct ReplicaStore {
+    pub(crate) sequences: Replicas<Sequence>,
+    pub(crate) registers: Replicas<Register>,
+}
+
+impl ReplicaStore {
+    /// Create a store holding replicas in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the store held in the given directory, creating it if needed. The replicas written
+    /// by a previous run of a client are loaded once the store is set for that client.
+    ///
+    /// Replicas of private data are written to disk as they are, so the directory should only
+    /// be readable by the owner of the client's keypair.
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir)?;
+        debug!("Opened replica store at {}", dir.display());
+
+        Ok(Self {
+            sequences: Replicas::persisted(dir, "sequence"),
+            registers: Replicas::persisted(dir, "register"),
+        })
+    }
+
+    // Hold the replicas of the given actor, loading those it wrote to disk in a previous run
+    pub(crate) fn load_actor(&mut self, actor: &PublicKey) {
+        self.sequences.load_actor(actor);
+        self.registers.load_actor(actor);
+    }
+}
+
+/// Data which can be replicated locally, by applying the same operations as the network does.
+pub(crate) trait Crdt: Clone + Serialize + DeserializeOwned + Send + 'static {
+    type Address: Copy + Debug + Eq + Hash + Serialize + DeserializeOwned;
+    type Op: Clone + PartialEq + Serialize + DeserializeOwned + Send + 'static;
+
+    fn apply(&mut self, op: Self::Op) -> Result<(), Error>;
+
+    fn num_entries(&self) -> Result<u64, Error>;
+}
+
+impl Crdt for Sequence {
+    type Address = SequenceAddress;
+    type Op = SequenceOp<SequenceEntry>;
+
+    fn apply(&mut self, op: Self::Op) -> Result<(), Error> {
+        Ok(self.apply_op(op)?)
+    }
+
+    fn num_entries(&self) -> Result<u64, Error> {
+        Ok(self.len(None)?)
+    }
+}
+
+impl Crdt for Register {
+    type Address = RegisterAddress;
+    type Op = RegisterOp<Entry>;
+
+    fn apply(&mut self, op: Self::Op) -> Result<(), Error> {
+        Ok(self.apply_op(op)?)
+    }
+
+    fn num_entries(&self) -> Result<u64, Error> {
+        Ok(self.size(None)?)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Replica<D, O> {
+    data: D,
+    // Operations written locally which haven't been sent to the network yet
+    unsent_ops: Vec<O>,
+}
+
+type ReplicaOf<D> = Replica<D, <D as Crdt>::Op>;
+
+/// Replicas of one type of data, keyed by their address.
+pub(crate) struct Replicas<D: Crdt> {
+    dir: Option<PathBuf>,
+    kind: &'static str,
+    // Hex encoded key of the actor whose replicas are held, prefixing the files they're kept in
+    actor: Option<String>,
+    replicas: Mutex<HashMap<D::Address, ReplicaOf<D>>>,
+    // Held while a replica is changed and written to disk, so that the files are written in
+    // the order the replicas changed. The replicas themselves are never locked during I/O.
+    disk: AsyncMutex<()>,
+}
+
+impl<D: Crdt> Default for Replicas<D> {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            kind: "",
+            actor: None,
+            replicas: Mutex::new(HashMap::new()),
+            disk: AsyncMutex::new(()),
+        }
+    }
+}
+
+impl<D: Crdt> Replicas<D> {
+    fn persisted(dir: &Path, kind: &'static str) -> Self {
+        Self {
+            dir: Some(dir.to_path_buf()),
+            kind,
+            ..Self::default()
+        }
+    }
+
+    /// Get the local replica of the data, including the writes not sent to the network yet.
+    pub(crate) fn get(&self, address: &D::Address) -> Option<D> {
+        self.lock().get(address).map(|replica| replica.data.clone())
+    }
+
+    /// Whether there is a local replica of the data.
+    pub(crate) fn contains(&self, address: &D::Address) -> bool {
+        self.lock().contains_key(address)
+    }
+
+    /// Merge the data fetched from the network into the local replica, returning the result.
+    ///
+    /// The network's data is taken as the base, and the local writes not sent yet are applied
+    /// on top of it. Writes already sent aren't, so those the network rejected are dropped.
+    pub(crate) async fn merge(&self, address: D::Address, network_data: D) -> D {
+        self.update(&address, |replicas| {
+            let mut unsent_ops = replicas
+                .get(&address)
+                .map(|replica| replica.unsent_ops.clone())
+                .unwrap_or_default();
+
+            let mut data = network_data;
+            unsent_ops.retain(|op| match apply_if_missing(&mut data, op.clone()) {
+                Ok(missing) => missing,
+                Err(error) => {
+                    warn!(
+                        "Dropping local write to {:?} which can't be applied: {:?}",
+                        address, error
+                    );
+                    false
+                }
+            });
+            trace!(
+                "Merged {:?} from the network, {} local write(s) not sent yet",
+                address,
+                unsent_ops.len()
+            );
+
+            let replica = Replica {
+                data: data.clone(),
+                unsent_ops,
+            };
+            let _ = replicas.insert(address, replica);
+            data
+        })
+        .await
+    }
+
+    /// Write to the local replica, with `write` applying the operation it creates to the data
+    /// it's given. The operation is then tracked until it's sent to the network.
+    pub(crate) async fn write<T, F>(
+        &self,
+        address: &D::Address,
+        write: F,
+    ) -> Result<(T, D::Op), Error>
+    where
+        F: FnOnce(&mut D) -> Result<(T, D::Op), Error>,
+    {
+        self.update(address, |replicas| {
+            let replica = replicas
+                .get_mut(address)
+                .ok_or(Error::NetworkDataError(sn_data_types::Error::NoSuchData))?;
+
+            let (output, op) = write(&mut replica.data)?;
+            replica.unsent_ops.push(op.clone());
+
+            Ok((output, op))
+        })
+        .await
+    }
+
+    /// Stop tracking a local operation once it has been sent to the network. It's then only
+    /// part of the replica if the network holds it, once merged in again.
+    pub(crate) async fn mark_sent(&self, address: &D::Address, op: &D::Op) {
+        self.update(address, |replicas| {
+            if let Some(replica) = replicas.get_mut(address) {
+                replica.unsent_ops.retain(|unsent| unsent != op);
+            }
+        })
+        .await
+    }
+
+    /// Local operations which haven't been sent to the network yet, in the order they were made.
+    pub(crate) fn unsent(&self, address: &D::Address) -> Vec<D::Op> {
+        self.lock()
+            .get(address)
+            .map(|replica| replica.unsent_ops.clone())
+            .unwrap_or_default()
+    }
+
+    /// Addresses of the replicas holding local operations which haven't been sent to the network
+    /// yet, including those written to disk by a previous run.
+    pub(crate) fn with_unsent(&self) -> Vec<D::Address> {
+        self.lock()
+            .iter()
+            .filter(|(_, replica)| !replica.unsent_ops.is_empty())
+            .map(|(address, _)| *address)
+            .collect()
+    }
+
+    /// Drop the local replica of the data.
+    pub(crate) async fn remove(&self, address: &D::Address) {
+        self.update(address, |replicas| {
+            let _ = replicas.remove(address);
+        })
+        .await
+    }
+
+    // Change the replicas in memory, then write the replica of the given address to disk, or
+    // remove its file if it's no longer held, should the store be persisted and the replica
+    // have changed. Failing to write it only loses the replica for later runs, so it's not
+    // reported to the caller.
+    async fn update<T, F>(&self, address: &D::Address, update: F) -> T
+    where
+        F: FnOnce(&mut HashMap<D::Address, ReplicaOf<D>>) -> T,
+    {
+        let path = self.path(address);
+        let _disk = self.disk.lock().await;
+        let (output, before, after) = {
+            let mut replicas = self.lock();
+            let encoded = |replicas: &HashMap<D::Address, ReplicaOf<D>>| {
+                path.as_ref()
+                    .and(replicas.get(address))
+                    .map(bincode::serialize)
+            };
+            let before = encoded(&replicas).and_then(Result::ok);
+            let output = update(&mut replicas);
+            (output, before, encoded(&replicas))
+        };
+
+        let path = match path {
+            Some(path) => path,
+            None => return output,
+        };
+        if before.as_ref() == after.as_ref().and_then(|bytes| bytes.as_ref().ok()) {
+            trace!("Replica of {:?} unchanged, not saving it", address);
+            return output;
+        }
+        let result = blocking(move || {
+            match after {
+                Some(bytes) => {
+                    let bytes =
+                        bytes.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                    // Write to a temporary file first so that no partial replica is ever loaded
+                    let tmp_path = path.with_extension("tmp");
+                    fs::write(&tmp_path, bytes)?;
+                    fs::rename(&tmp_path, &path)
+                }
+                None if path.exists() => fs::remove_file(&path),
+                None => Ok(()),
+            }
+        })
+        .await;
+        if let Err(error) = result {
+            warn!("Failed to save replica of {:?}: {:?}", address, error);
+        }
+
+        output
+    }
+
+    // Hold the replicas of the given actor, loading those written to disk by a previous run.
+    // This happens once, before the store is shared with the client.
+    fn load_actor(&mut self, actor: &PublicKey) {
+        let actor = match bincode::serialize(actor) {
+            Ok(bytes) => hex(&bytes),
+            Err(error) => {
+                warn!(
+                    "Failed to encode replica store actor {:?}: {:?}",
+                    actor, error
+                );
+                return;
+            }
+        };
+        self.actor = Some(actor);
+
+        let mut loaded = HashMap::new();
+        for address in self.persisted_addresses() {
+            let path = match self.path(&address) {
+                Some(path) => path,
+                None => continue,
+            };
+            let replica = fs::read(&path)
+                .map_err(Error::from)
+                .and_then(|bytes| Ok(bincode::deserialize(&bytes)?));
+            match replica {
+                Ok(replica) => {
+                    trace!("Loaded replica of {:?} from disk", address);
+                    let _ = loaded.insert(address, replica);
+                }
+                Err(error) => warn!("Failed to load replica of {:?}: {:?}", address, error),
+            }
+        }
+        *self
+            .replicas
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = loaded;
+    }
+
+    fn path(&self, address: &D::Address) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        let actor = self.actor.as_ref()?;
+        let address = bincode::serialize(address).ok()?;
+        Some(dir.join(format!("{}_{}_{}", actor, self.kind, hex(&address))))
+    }
+
+    // Addresses of the actor's replicas written to disk, decoded from their file names
+    fn persisted_addresses(&self) -> Vec<D::Address> {
+        let (dir, actor) = match (&self.dir, &self.actor) {
+            (Some(dir), Some(actor)) => (dir, actor),
+            _ => return Vec::new(),
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn!("Failed to list the replicas on disk: {:?}", error);
+                return Vec::new();
+            }
+        };
+        let prefix = format!("{}_{}_", actor, self.kind);
+
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|file_name| {
+                let encoded = file_name.strip_prefix(&prefix)?;
+                let bytes = (0..encoded.len())
+                    .step_by(2)
+                    .map(|index| u8::from_str_radix(encoded.get(index..index + 2)?, 16).ok())
+                    .collect::<Option<Vec<u8>>>()?;
+                bincode::deserialize(&bytes).ok()
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<D::Address, ReplicaOf<D>>> {
+        self.replicas.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Apply the operation unless the data already holds it, returning whether it was applied
+fn apply_if_missing<D: Crdt>(data: &mut D, op: D::Op) -> Result<bool, Error> {
+    let entries = data.num_entries()?;
+    data.apply(op)?;
+    Ok(data.num_entries()? > entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use rand::rngs::OsRng;
+    use sn_data_types::{Keypair, SequencePublicPermissions, SequencePublicPolicy, SequenceUser};
+    use std::{collections::BTreeMap, env::temp_dir};
+    use xor_name::XorName;
+
+    fn new_sequence(keypair: &Keypair) -> Sequence {
+        let owner = keypair.public_key();
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            SequenceUser::Key(owner),
+            SequencePublicPermissions::new(true),
+        );
+        let policy = SequencePublicPolicy { owner, permissions };
+        Sequence::new_public(
+            owner,
+            owner.to_string(),
+            XorName::random(),
+            10,
+            Some(policy),
+        )
+    }
+
+    async fn append(
+        replicas: &Replicas<Sequence>,
+        keypair: &Keypair,
+        address: &SequenceAddress,
+        entry: &[u8],
+    ) -> Result<SequenceOp<SequenceEntry>> {
+        let ((), op) = replicas
+            .write(address, |sequence| {
+                let mut op = sequence.create_unsigned_append_op(entry.to_vec())?;
+                let bytes = bincode::serialize(&op.crdt_op)?;
+                op.signature = Some(keypair.sign(&bytes));
+                sequence.apply_op(op.clone())?;
+                Ok(((), op))
+            })
+            .await?;
+        Ok(op)
+    }
+
+    fn open_store(dir: &Path, keypair: &Keypair) -> Result<ReplicaStore> {
+        let mut store = ReplicaStore::open(dir)?;
+        store.load_actor(&keypair.public_key());
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn replica_keeps_local_writes_until_sent() -> Result<()> {
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let replicas = Replicas::<Sequence>::default();
+        let network_sequence = new_sequence(&keypair);
+        let address = *network_sequence.address();
+
+        // writing requires the data to have been synced first
+        assert!(append(&replicas, &keypair, &address, b"first")
+            .await
+            .is_err());
+
+        let _ = replicas.merge(address, network_sequence.clone()).await;
+        let op = append(&replicas, &keypair, &address, b"first").await?;
+        assert_eq!(replicas.unsent(&address), vec![op.clone()]);
+
+        // the network doesn't hold the write yet, so it's applied on top of its data
+        let merged = replicas.merge(address, network_sequence.clone()).await;
+        assert_eq!(merged.len(None)?, 1);
+        assert_eq!(replicas.with_unsent(), vec![address]);
+
+        // once sent, it's no longer tracked, so it's dropped should the network reject it
+        replicas.mark_sent(&address, &op).await;
+        assert!(replicas.unsent(&address).is_empty());
+        assert!(replicas.with_unsent().is_empty());
+        let merged = replicas.merge(address, network_sequence.clone()).await;
+        assert_eq!(merged.len(None)?, 0);
+
+        // and it's part of the replica once the network holds it
+        let mut network_sequence = network_sequence;
+        network_sequence.apply_op(op)?;
+        let merged = replicas.merge(address, network_sequence).await;
+        assert_eq!(merged.len(None)?, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicas_are_kept_across_opens() -> Result<()> {
+        let dir = temp_dir().join(format!("sn_client_replicas_{:x}", rand::random::<u64>()));
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let sequence = new_sequence(&keypair);
+        let address = *sequence.address();
+        let op = {
+            let store = open_store(&dir, &keypair)?;
+            let _ = store.sequences.merge(address, sequence).await;
+            append(&store.sequences, &keypair, &address, b"offline").await?
+        };
+
+        let store = open_store(&dir, &keypair)?;
+        let replica = store.sequences.get(&address);
+        assert_eq!(
+            replica.map(|sequence| sequence.len(None)).transpose()?,
+            Some(1)
+        );
+        assert_eq!(store.sequences.unsent(&address), vec![op]);
+
+        // the replicas of other clients sharing the directory are kept apart
+        let other_keypair = Keypair::new_ed25519(&mut OsRng);
+        let other_store = open_store(&dir, &other_keypair)?;
+        assert!(!other_store.sequences.contains(&address));
+        assert!(other_store.sequences.with_unsent().is_empty());
+
+        store.sequences.remove(&address).await;
+        assert!(!open_store(&dir, &keypair)?.sequences.contains(&address));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicas_are_only_saved_when_changed() -> Result<()> {
+        let dir = temp_dir().join(format!("sn_client_replicas_{:x}", rand::random::<u64>()));
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let sequence = new_sequence(&keypair);
+        let address = *sequence.address();
+        let store = open_store(&dir, &keypair)?;
+        let path = store
+            .sequences
+            .path(&address)
+            .ok_or_else(|| anyhow!("No path for a persisted replica"))?;
+
+        let _ = store.sequences.merge(address, sequence.clone()).await;
+        assert!(path.exists());
+
+        // merging the same data again leaves the file as it is
+        fs::remove_file(&path)?;
+        let _ = store.sequences.merge(address, sequence.clone()).await;
+        assert!(!path.exists());
+
+        // while a change is saved
+        let _ = append(&store.sequences, &keypair, &address, b"changed").await?;
+        assert!(path.exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -11,7 +11,7 @@ use crate::Error;
 use log::{debug, trace};
 use sn_data_types::{
     PublicKey, Sequence, SequenceAddress, SequenceEntries, SequenceEntry, SequenceIndex,
-    SequencePermissions, SequencePrivatePermissions, SequencePrivatePolicy,
+    SequenceOp, SequencePermissions, SequencePrivatePermissions, SequencePrivatePolicy,
     SequencePublicPermissions, SequencePublicPolicy, SequenceUser,
 };
 use sn_messaging::client::{
@@ -79,6 +79,8 @@ impl Client {
         owner: PublicKey,
         permissions: BTreeMap<PublicKey, SequencePrivatePermissions>,
     ) -> Result<SequenceAddress, Error> {
+        self.check_writable()?;
+
         trace!("Store Private Sequence Data {:?}", name);
         let pk = self.public_key();
         let policy = SequencePrivatePolicy { owner, permissions };
@@ -150,6 +152,8 @@ impl Client {
         owner: PublicKey,
         permissions: BTreeMap<SequenceUser, SequencePublicPermissions>,
     ) -> Result<SequenceAddress, Error> {
+        self.check_writable()?;
+
         trace!("Store Public Sequence Data {:?}", name);
         let pk = self.public_key();
         let policy = SequencePublicPolicy { owner, permissions };
@@ -212,6 +216,8 @@ impl Client {
     /// # let balance_after_write = client.get_local_balance().await; assert_ne!(initial_balance, balance_after_write); Ok(()) } ); }
     /// ```
     pub async fn delete_sequence(&self, address: SequenceAddress) -> Result<(), Error> {
+        self.check_writable()?;
+
         let cmd = DataCmd::Sequence(SequenceWrite::Delete(address));
         // Payment for PUT
         let payment_proof = self.create_write_payment_proof(&cmd).await?;
@@ -223,6 +229,7 @@ impl Client {
         };
 
         self.send_cmd(cmd).await?;
+        self.replicas.sequences.remove(&address).await;
 
         self.apply_write_payment_to_local_actor(payment_proof).await
     }
@@ -269,21 +276,140 @@ impl Client {
         address: SequenceAddress,
         entry: SequenceEntry,
     ) -> Result<(), Error> {
-        // First we fetch it so we can get the causality info,
-        // either from local CRDT replica or from the network if not found
-        let mut sequence = self.get_sequence(address).await?;
+        self.check_writable()?;
 
-        // We can now append the entry to the Sequence
-        let mut op = sequence.create_unsigned_append_op(entry)?;
-        let bytes = bincode::serialize(&op.crdt_op)?;
-        let signature = self.keypair.sign(&bytes);
-        op.signature = Some(signature);
-        sequence.apply_op(op.clone())?;
+        let op = self.append_to_local_sequence(address, entry).await?;
 
         // Finally we can send the mutation to the network's replicas
-        let cmd = DataCmd::Sequence(SequenceWrite::Edit(op));
+        self.send_sequence_op(address, op).await
+    }
 
-        self.pay_and_send_data_command(cmd).await
+    /// Append to the local replica of a Sequence only, queueing the append in the outbox
+    /// until `flush_outbox` is called, e.g. once the network is reachable again.
+    ///
+    /// The Sequence is fetched from the network if there is no local replica of it yet.
+    /// Appends made concurrently by others are merged in once the outbox is flushed.
+    /// Queued appends are lost when the client is dropped, unless its replica store was
+    /// opened from a directory, see `set_replica_store`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::SequenceAddress;
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let address = SequenceAddress::Public { name: XorName::random(), tag: 10 };
+    ///
+    /// client.queue_sequence_append(address, b"First Entry".to_vec()).await?;
+    /// client.queue_sequence_append(address, b"Second Entry".to_vec()).await?;
+    /// // Each append is paid for with a transfer of its own
+    /// let _sent = client.flush_outbox().await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn queue_sequence_append(
+        &self,
+        address: SequenceAddress,
+        entry: SequenceEntry,
+    ) -> Result<(), Error> {
+        self.check_writable()?;
+
+        let _ = self.append_to_local_sequence(address, entry).await?;
+        Ok(())
+    }
+
+    // Append to the local replica, returning the signed op to send to the network
+    async fn append_to_local_sequence(
+        &self,
+        address: SequenceAddress,
+        entry: SequenceEntry,
+    ) -> Result<SequenceOp<SequenceEntry>, Error> {
+        // First we need the causality info, either from the local CRDT replica
+        // or from the network if we don't have a replica yet
+        if !self.replicas.sequences.contains(&address) {
+            let _ = self.fetch_and_merge_sequence(address).await?;
+        }
+
+        // We can now append the entry to the local replica
+        let ((), op) = self
+            .replicas
+            .sequences
+            .write(&address, |sequence| {
+                let mut op = sequence.create_unsigned_append_op(entry)?;
+                let bytes = bincode::serialize(&op.crdt_op)?;
+                let signature = self.keypair.sign(&bytes);
+                op.signature = Some(signature);
+                sequence.apply_op(op.clone())?;
+                Ok(((), op))
+            })
+            .await?;
+
+        Ok(op)
+    }
+
+    /// Send to the network the appends made to the local replica of a Sequence which couldn't
+    /// be sent at the time, e.g. while the network was unreachable, merging in the Sequence
+    /// held by the network first.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::SequenceAddress;
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let address = SequenceAddress::Public { name: XorName::random(), tag: 10 };
+    ///
+    /// if client.append_to_sequence(address, b"New Entry Value".to_vec()).await.is_err() {
+    ///     // The entry is kept in the local replica, and sent once the network is reachable
+    ///     client.sync_sequence(address).await?;
+    /// }
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn sync_sequence(&self, address: SequenceAddress) -> Result<(), Error> {
+        self.check_writable()?;
+
+        let _ = self.fetch_and_merge_sequence(address).await?;
+        let ops = self.replicas.sequences.unsent(&address);
+        debug!("Sending {} pending append(s) to {:?}", ops.len(), address);
+        for op in ops {
+            self.send_sequence_op(address, op).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the local replica of a Sequence, including the appends not yet sent to the network,
+    /// without fetching it from the network. This is `None` if this client never appended to,
+    /// nor synced, the Sequence.
+    pub fn get_local_sequence(&self, address: SequenceAddress) -> Option<Sequence> {
+        self.replicas.sequences.get(&address)
+    }
+
+    // Fetch the Sequence from the network and merge it into the local replica, which may hold
+    // appends the network doesn't have yet, returning the result
+    pub(crate) async fn fetch_and_merge_sequence(
+        &self,
+        address: SequenceAddress,
+    ) -> Result<Sequence, Error> {
+        let sequence = self.get_sequence(address).await?;
+        Ok(self.replicas.sequences.merge(address, sequence).await)
+    }
+
+    async fn send_sequence_op(
+        &self,
+        address: SequenceAddress,
+        op: SequenceOp<SequenceEntry>,
+    ) -> Result<(), Error> {
+        let cmd = DataCmd::Sequence(SequenceWrite::Edit(op.clone()));
+        self.pay_and_send_data_command(cmd).await?;
+        self.replicas.sequences.mark_sent(&address, &op).await;
+        Ok(())
     }
 
     /// Store a new public sequenced data object
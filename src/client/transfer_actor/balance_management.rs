@@ -10,13 +10,18 @@ use sn_data_types::{PublicKey, SignedTransfer, Token, TransferAgreementProof};
 use sn_messaging::client::{Cmd, Event, Query, QueryResponse, TransferCmd, TransferQuery};
 use sn_transfers::{ActorEvent, TransferInitiated};
 
-use crate::{Client, Error};
+use crate::{connections::CmdErrors, Client, Error};
 
+use futures::future;
 use log::{debug, info, trace};
 
+// Maximum length of the memo attached to a transfer, in bytes
+const MAX_MEMO_LEN: usize = 256;
+
 /// Handle all token transfers and Write API requests for a given ClientId.
 impl Client {
     /// Get the current known account balance from the local actor. (ie. Without querying the network)
+    /// Read-only clients have no local actor, so their local balance is always zero.
     ///
     /// # Examples
     ///
@@ -37,7 +42,10 @@ impl Client {
     /// ```
     pub async fn get_local_balance(&self) -> Token {
         info!("Retrieving actor's local balance.");
-        self.transfer_actor.read().await.balance()
+        match self.transfer_actor() {
+            Ok(actor) => actor.read().await.balance(),
+            Err(_) => Token::from_nano(0),
+        }
     }
 
     /// Handle a validation event.
@@ -51,7 +59,7 @@ impl Client {
             Event::TransferValidated { event, .. } => event,
             _ => return Err(Error::UnexpectedTransferEvent(event)),
         };
-        let mut actor = self.transfer_actor.write().await;
+        let mut actor = self.transfer_actor()?.write().await;
         let transfer_validation = match actor.receive(validation) {
             Ok(Some(validation)) => validation,
             Ok(None) => return Ok(None),
@@ -131,21 +139,228 @@ impl Client {
         to: PublicKey,
         amount: Token,
     ) -> Result<(u64, PublicKey), Error> {
+        self.send_tokens_with_memo(to, amount, "").await
+    }
+
+    /// Send token to another PublicKey, attaching a memo to the transfer, e.g. the reference
+    /// of the invoice it pays. The memo, of up to 256 bytes, is found in the `msg` of the
+    /// records returned by `get_transfer_history`, both for the sender and the recipient.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio;use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::{PublicKey, Token};
+    /// use std::str::FromStr;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// let sk = threshold_crypto::SecretKey::random();
+    /// let pk = PublicKey::from(sk.public_key());
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let _ = client
+    ///     .send_tokens_with_memo(pk, Token::from_str("1")?, "Invoice #1234")
+    ///     .await?;
+    /// # Ok(())} ); }
+    /// ```
+    pub async fn send_tokens_with_memo(
+        &self,
+        to: PublicKey,
+        amount: Token,
+        memo: &str,
+    ) -> Result<(u64, PublicKey), Error> {
+        self.check_writable()?;
+
+        if memo.len() > MAX_MEMO_LEN {
+            return Err(Error::MemoTooLong {
+                len: memo.len(),
+                max: MAX_MEMO_LEN,
+            });
+        }
+
         info!("Sending token");
 
         // first make sure our balance  history is up to date
         self.get_history().await?;
 
+        self.transfer_tokens(to, amount, memo).await
+    }
+
+    /// Send token to several PublicKeys, one transfer per recipient.
+    ///
+    /// The transfers are made in the given order, as each debit follows the previous one.
+    /// Only the balance history sync is shared by the whole batch, rather than made before
+    /// each transfer as `send_tokens` does, and the registration of each transfer on the
+    /// network is awaited while the next one is being validated. The outcome of each transfer
+    /// attempted is returned, in the order of the recipients. The batch stops at the first
+    /// transfer which fails, e.g. for lack of balance, so the transfers after it are not
+    /// attempted and have no outcome, while those before it remain done.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio;use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::{PublicKey, Token};
+    /// use std::str::FromStr;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// let pk1 = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+    /// let pk2 = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let outcomes = client
+    ///     .send_tokens_batch(vec![(pk1, Token::from_str("1")?), (pk2, Token::from_str("2")?)])
+    ///     .await?;
+    /// for outcome in outcomes {
+    ///     let (count, _sending_pk) = outcome?;
+    ///     println!("Transfer #{} sent", count);
+    /// }
+    /// # Ok(())} ); }
+    /// ```
+    pub async fn send_tokens_batch(
+        &self,
+        transfers: Vec<(PublicKey, Token)>,
+    ) -> Result<Vec<Result<(u64, PublicKey), Error>>, Error> {
+        let transfers = transfers
+            .into_iter()
+            .map(|(to, amount)| (to, amount, String::new()))
+            .collect();
+
+        self.send_tokens_batch_with_memos(transfers).await
+    }
+
+    /// Send token to several PublicKeys as `send_tokens_batch` does, attaching a memo to each
+    /// transfer as `send_tokens_with_memo` does. An empty memo attaches none. No transfer is
+    /// attempted if any memo is too long.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio;use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// use sn_data_types::{PublicKey, Token};
+    /// use std::str::FromStr;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// let pk1 = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+    /// let pk2 = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let outcomes = client
+    ///     .send_tokens_batch_with_memos(vec![
+    ///         (pk1, Token::from_str("1")?, "Invoice #1234".to_string()),
+    ///         (pk2, Token::from_str("2")?, "Invoice #1235".to_string()),
+    ///     ])
+    ///     .await?;
+    /// assert_eq!(outcomes.len(), 2);
+    /// # Ok(())} ); }
+    /// ```
+    pub async fn send_tokens_batch_with_memos(
+        &self,
+        transfers: Vec<(PublicKey, Token, String)>,
+    ) -> Result<Vec<Result<(u64, PublicKey), Error>>, Error> {
+        self.check_writable()?;
+
+        for (_, _, memo) in &transfers {
+            if memo.len() > MAX_MEMO_LEN {
+                return Err(Error::MemoTooLong {
+                    len: memo.len(),
+                    max: MAX_MEMO_LEN,
+                });
+            }
+        }
+
+        info!("Sending token to {} recipients", transfers.len());
+
+        // first make sure our balance  history is up to date
+        self.get_history().await?;
+
+        let mut outcomes = Vec::with_capacity(transfers.len());
+        // The transfer sent to be registered, whose errors are awaited along the next validation
+        let mut registering = None;
+        for (to, amount, memo) in transfers {
+            let validated = match registering.take() {
+                Some((dot, reported_errors)) => {
+                    let (registered, validated) = future::join(
+                        self.await_cmd_errors(reported_errors),
+                        self.validate_transfer(to, amount, &memo),
+                    )
+                    .await;
+
+                    if let Err(error) = registered {
+                        debug!("Registering transfer #{} failed, stopping the batch", dot.0);
+                        outcomes.push(Err(error));
+                        break;
+                    }
+                    outcomes.push(Ok(dot));
+                    validated
+                }
+                None => self.validate_transfer(to, amount, &memo).await,
+            };
+
+            let sent = match validated {
+                Ok((dot, transfer_proof)) => self
+                    .dispatch_registration(transfer_proof)
+                    .await
+                    .map(|reported_errors| (dot, reported_errors)),
+                Err(error) => Err(error),
+            };
+
+            match sent {
+                Ok(sent) => registering = Some(sent),
+                Err(error) => {
+                    debug!(
+                        "Transfer to {:?} failed, stopping the batch after {} transfers",
+                        to,
+                        outcomes.len() + 1
+                    );
+                    outcomes.push(Err(error));
+                    break;
+                }
+            }
+        }
+
+        if let Some((dot, reported_errors)) = registering {
+            outcomes.push(self.await_cmd_errors(reported_errors).await.map(|()| dot));
+        }
+
+        Ok(outcomes)
+    }
+
+    // Transfer token from the local actor's balance, which is expected to be in sync already
+    async fn transfer_tokens(
+        &self,
+        to: PublicKey,
+        amount: Token,
+        memo: &str,
+    ) -> Result<(u64, PublicKey), Error> {
+        let (dot, transfer_proof) = self.validate_transfer(to, amount, memo).await?;
+        let reported_errors = self.dispatch_registration(transfer_proof).await?;
+        self.await_cmd_errors(reported_errors).await?;
+
+        Ok(dot)
+    }
+
+    // Initiate a transfer with the local actor and get it validated by the section. The
+    // transfer is registered with the local actor, so the next one can follow its debit.
+    async fn validate_transfer(
+        &self,
+        to: PublicKey,
+        amount: Token,
+        memo: &str,
+    ) -> Result<((u64, PublicKey), TransferAgreementProof), Error> {
         info!(
             "Our actor balance at send: {:?}",
-            self.transfer_actor.read().await.balance()
+            self.transfer_actor()?.read().await.balance()
         );
 
         let initiated = self
-            .transfer_actor
+            .transfer_actor()?
             .read()
             .await
-            .transfer(amount, to, "".to_string())?
+            .transfer(amount, to, memo.to_string())?
             .ok_or(Error::NoTransferGenerated)?;
 
         let signed_transfer = SignedTransfer {
@@ -155,7 +370,7 @@ impl Client {
         let dot = signed_transfer.id();
         let cmd = Cmd::Transfer(TransferCmd::ValidateTransfer(signed_transfer.clone()));
 
-        self.transfer_actor
+        self.transfer_actor()?
             .write()
             .await
             .apply(ActorEvent::TransferInitiated(TransferInitiated {
@@ -166,25 +381,29 @@ impl Client {
         let transfer_proof: TransferAgreementProof =
             self.await_validation(cmd, signed_transfer.id()).await?;
 
-        // Register the transfer on the network.
-        let cmd = Cmd::Transfer(TransferCmd::RegisterTransfer(transfer_proof.clone()));
+        let mut actor = self.transfer_actor()?.write().await;
+        let register_event = actor
+            .register(transfer_proof.clone())?
+            .ok_or(Error::NoTransferEventsForLocalActor)?;
+
+        actor.apply(ActorEvent::TransferRegistrationSent(register_event))?;
+
+        Ok(((dot.counter, dot.actor), transfer_proof))
+    }
 
+    // Send a validated transfer to be registered on the network, leaving the errors reported
+    // for it to be awaited.
+    async fn dispatch_registration(
+        &self,
+        transfer_proof: TransferAgreementProof,
+    ) -> Result<CmdErrors, Error> {
         trace!(
             "Transfer proof received and to be sent in RegisterTransfer req: {:?}",
             transfer_proof
         );
 
-        self.send_cmd(cmd).await?;
-
-        let mut actor = self.transfer_actor.write().await;
-        // First register with local actor, then reply.
-        let register_event = actor
-            .register(transfer_proof)?
-            .ok_or(Error::NoTransferEventsForLocalActor)?;
-
-        actor.apply(ActorEvent::TransferRegistrationSent(register_event))?;
-
-        Ok((dot.counter, dot.actor))
+        let cmd = Cmd::Transfer(TransferCmd::RegisterTransfer(transfer_proof));
+        self.dispatch_cmd(cmd).await
     }
 }
 
@@ -203,7 +422,7 @@ mod tests {
     use anyhow::{anyhow, bail, Result};
     use rand::rngs::OsRng;
     use sn_data_types::{Keypair, Token};
-    use std::str::FromStr;
+    use std::{str::FromStr, time::Duration};
 
     #[tokio::test]
     pub async fn transfer_actor_can_send_tokens_and_thats_reflected_locally() -> Result<()> {
@@ -431,4 +650,114 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    pub async fn transfer_memo_is_found_in_history() -> Result<()> {
+        let client = create_test_client().await?;
+        let receiver = Keypair::new_ed25519(&mut OsRng).public_key();
+
+        let _ = client
+            .send_tokens_with_memo(receiver, Token::from_str("1")?, "Invoice #1234")
+            .await?;
+
+        let history = client.get_transfer_history(client.public_key(), 0).await?;
+        assert_eq!(history.debits.len(), 1);
+        assert_eq!(history.debits[0].msg, "Invoice #1234");
+
+        let receiver_history = client.get_transfer_history(receiver, 0).await?;
+        assert_eq!(receiver_history.credits.len(), 1);
+        assert_eq!(receiver_history.credits[0].msg, "Invoice #1234");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn transfer_memo_cannot_be_too_long() -> Result<()> {
+        let client = create_test_client().await?;
+        let receiver = Keypair::new_ed25519(&mut OsRng).public_key();
+        let memo = "a".repeat(MAX_MEMO_LEN + 1);
+
+        match client
+            .send_tokens_with_memo(receiver, Token::from_str("1")?, &memo)
+            .await
+        {
+            Err(Error::MemoTooLong { len, max }) => {
+                assert_eq!(len, MAX_MEMO_LEN + 1);
+                assert_eq!(max, MAX_MEMO_LEN);
+            }
+            other => bail!("Unexpected result: {:?}", other),
+        }
+        assert_eq!(client.get_local_balance().await, Token::from_str("10")?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn transfer_batch_stops_on_insufficient_balance() -> Result<()> {
+        let client = create_test_client().await?;
+        let receivers: Vec<_> = (0..4)
+            .map(|_| Keypair::new_ed25519(&mut OsRng).public_key())
+            .collect();
+
+        let outcomes = client
+            .send_tokens_batch_with_memos(vec![
+                (receivers[0], Token::from_str("1")?, String::new()),
+                (
+                    receivers[1],
+                    Token::from_str("2")?,
+                    "Invoice #1234".to_string(),
+                ),
+                (receivers[2], Token::from_str("5000")?, String::new()),
+                (receivers[3], Token::from_str("1")?, String::new()),
+            ])
+            .await?;
+
+        assert_eq!(outcomes.len(), 3);
+        match &outcomes[..] {
+            [Ok(_), Ok(_), Err(Error::Transfer(TransfersError::InsufficientBalance))] => (),
+            other => bail!("Unexpected batch outcomes: {:?}", other),
+        }
+
+        // Initial 10 token on creation from farming simulation minus 3
+        assert_eq!(client.get_local_balance().await, Token::from_str("7")?);
+        let _ = retry_loop_for_pattern!( client.get_balance(), Ok(bal) if *bal == Token::from_str("7")?);
+        let _ = retry_loop_for_pattern!( client.get_balance_for(receivers[1]), Ok(bal) if *bal == Token::from_str("2")?);
+
+        let history = client.get_transfer_history(receivers[1], 0).await?;
+        assert_eq!(history.credits.len(), 1);
+        assert_eq!(history.credits[0].msg, "Invoice #1234");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn transfer_batch_awaits_registrations_along_validations() -> Result<()> {
+        let mut client = create_test_client().await?;
+        client.set_cmd_error_wait(Duration::from_millis(500));
+        let receivers: Vec<_> = (0..3)
+            .map(|_| Keypair::new_ed25519(&mut OsRng).public_key())
+            .collect();
+
+        let amount = Token::from_str("1")?;
+        let outcomes = client
+            .send_tokens_batch(receivers.iter().map(|pk| (*pk, amount)).collect())
+            .await?;
+
+        let counters = outcomes
+            .into_iter()
+            .map(|outcome| outcome.map(|(counter, _)| counter))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(counters.len(), 3);
+        assert_eq!(counters[1], counters[0] + 1);
+        assert_eq!(counters[2], counters[1] + 1);
+
+        // Initial 10 token on creation from farming simulation minus 3
+        assert_eq!(client.get_local_balance().await, Token::from_str("7")?);
+        let _ = retry_loop_for_pattern!( client.get_balance(), Ok(bal) if *bal == Token::from_str("7")?);
+        for receiver in receivers {
+            let _ = retry_loop_for_pattern!( client.get_balance_for(receiver), Ok(bal) if *bal == Token::from_str("1")?);
+        }
+
+        Ok(())
+    }
 }
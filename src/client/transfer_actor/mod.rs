@@ -13,21 +13,51 @@ mod simulated_payouts;
 // Module containing all PUT apis
 mod write_apis;
 
-use crate::{Client, Error};
+use crate::{client::ClientEvent, utils::sign_client_msg, Client, Error};
 use bincode::serialize;
+use crdts::Dot;
 use log::{debug, error, info, trace, warn};
 use sn_data_types::{
-    DebitId, PublicKey, SignedTransfer, Token, TransferAgreementProof, TransferValidated,
+    ActorHistory, CreditAgreementProof, DebitId, PublicKey, SignedTransfer, Token,
+    TransferAgreementProof, TransferValidated,
 };
-use sn_messaging::client::{
-    ClientSigned, Cmd, DataCmd, Query, QueryResponse, TransferCmd, TransferQuery,
+use sn_messaging::{
+    client::{Cmd, DataCmd, Query, QueryResponse, TransferCmd, TransferQuery},
+    MessageId,
 };
 use sn_transfers::{ActorEvent, TransferInitiated};
+use std::collections::HashSet;
 use tokio::sync::mpsc::channel;
 
 /// Actual Transfer Actor
 pub use sn_transfers::TransferActor as SafeTransferActor;
 
+/// A transfer from or to a key, as found in its history.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferRecord {
+    /// Id of the transfer, i.e. the key it was sent from and the version of that key's debits.
+    pub id: Dot<PublicKey>,
+    /// Amount of tokens transferred.
+    pub amount: Token,
+    /// Key the tokens were sent to for debits, or received from for credits.
+    pub counterparty: PublicKey,
+    /// Message attached to the transfer.
+    pub msg: String,
+}
+
+/// The transfers from and to a key, as held by the network.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransferHistory {
+    /// The transfers received by the key.
+    pub credits: Vec<TransferRecord>,
+    /// The transfers sent from the key.
+    pub debits: Vec<TransferRecord>,
+    /// Version of the debits of the history, i.e. the number of transfers sent from the key.
+    /// Fetching the history since this version on the next call only gets the transfers sent
+    /// from then on.
+    pub version: u64,
+}
+
 impl Client {
     /// Get the client's current coin balance from the network
     ///
@@ -63,8 +93,8 @@ impl Client {
         //
         if let Err(error) = self.get_history().await {
             match error {
-                Error::ElderHistoryOutofDate => {
-                    // do nothing, we know the truth
+                Error::ElderHistoryOutofDate | Error::ReadOnlyClient => {
+                    // do nothing, we know the truth, or have no actor to sync
                 }
                 other_error => return Err(other_error),
             }
@@ -132,21 +162,14 @@ impl Client {
         let public_key = self.public_key();
         info!("Getting SnTransfers history for pk: {:?}", public_key);
 
-        let query = Query::Transfer(TransferQuery::GetHistory {
-            at: public_key,
-            since_version: 0,
-        });
+        // Read-only clients have no actor to sync, so the history isn't even fetched for them
+        let actor = self.transfer_actor()?;
 
-        // This is a normal response manager request. We want quorum on this for now...
-        let query_result = self.send_query(query).await?;
-        let msg_id = query_result.msg_id;
+        // The actor is synced from the whole history of the key
+        let history = self.fetch_history(public_key, 0).await?;
+        let credits = history.credits.clone();
 
-        let history = match query_result.response {
-            QueryResponse::GetHistory(history) => history.map_err(|err| Error::from((err, msg_id))),
-            _ => Err(Error::UnexpectedHistoryResponse(query_result.response)),
-        }?;
-
-        let mut actor = self.transfer_actor.write().await;
+        let mut actor = actor.write().await;
         match actor.from_history(history) {
             Ok(synced_transfer_outcome) => {
                 if let Some(transfers) = synced_transfer_outcome {
@@ -164,10 +187,125 @@ impl Client {
         }
 
         debug!("Current balance after GetHistory {:?}", actor.balance());
+        drop(actor);
+
+        self.notify_received_credits(&credits).await;
 
         Ok(())
     }
 
+    /// Get the transfers sent from and received by a key, since the given version of its
+    /// debits. The network indexes a history by the debits of the key, so fetching the history
+    /// since the `version` returned by the previous call only gets the debits made since then,
+    /// e.g. to build a statement of a wallet incrementally. The credits are all returned every
+    /// time, those already seen being told apart by their `id`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use sn_client::Client;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let history = client.get_transfer_history(client.public_key(), 0).await?;
+    /// for credit in history.credits {
+    ///     println!("Received {} from {}: {}", credit.amount, credit.counterparty, credit.msg);
+    /// }
+    /// // Later on, only get what's new
+    /// let _new = client.get_transfer_history(client.public_key(), history.version).await?;
+    /// # Ok(()) } ); }
+    /// ```
+    pub async fn get_transfer_history(
+        &self,
+        public_key: PublicKey,
+        since_version: u64,
+    ) -> Result<TransferHistory, Error> {
+        info!(
+            "Getting transfer history for pk: {:?}, since version {}",
+            public_key, since_version
+        );
+
+        let history = self.fetch_history(public_key, since_version).await?;
+
+        let credits = history
+            .credits
+            .iter()
+            .map(|proof| {
+                let credit = &proof.signed_credit.credit;
+                TransferRecord {
+                    id: credit.id,
+                    amount: credit.amount,
+                    counterparty: credit.id.actor,
+                    msg: credit.msg.clone(),
+                }
+            })
+            .collect();
+        let debits: Vec<_> = history
+            .debits
+            .iter()
+            .map(|proof| {
+                let credit = &proof.signed_credit.credit;
+                TransferRecord {
+                    id: proof.signed_debit.debit.id,
+                    amount: proof.signed_debit.debit.amount,
+                    counterparty: credit.recipient,
+                    msg: credit.msg.clone(),
+                }
+            })
+            .collect();
+        // Debits are versioned by the sender, one after the other from zero
+        let version = debits
+            .iter()
+            .map(|debit| debit.id.counter + 1)
+            .max()
+            .unwrap_or(since_version)
+            .max(since_version);
+
+        Ok(TransferHistory {
+            credits,
+            debits,
+            version,
+        })
+    }
+
+    // Query the network for the history of a key, since the given version of its debits
+    async fn fetch_history(
+        &self,
+        public_key: PublicKey,
+        since_version: u64,
+    ) -> Result<ActorHistory, Error> {
+        let query = Query::Transfer(TransferQuery::GetHistory {
+            at: public_key,
+            since_version: since_version as usize,
+        });
+
+        // This is a normal response manager request. We want quorum on this for now...
+        let query_result = self.send_query(query).await?;
+        let msg_id = query_result.msg_id;
+
+        match query_result.response {
+            QueryResponse::GetHistory(history) => history.map_err(|err| Error::from((err, msg_id))),
+            _ => Err(Error::UnexpectedHistoryResponse(query_result.response)),
+        }
+    }
+
+    // Let subscribers know about the credits of the history not seen before. The credits
+    // found on the first sync were received before the client started, so they're only
+    // recorded as seen.
+    async fn notify_received_credits(&self, credits: &[CreditAgreementProof]) {
+        let mut received = self.received_credits.write().await;
+        let first_sync = received.is_none();
+        let received = received.get_or_insert_with(HashSet::new);
+        for credit in credits {
+            if received.insert(*credit.signed_credit.id()) && !first_sync {
+                self.session
+                    .notify(ClientEvent::TransferReceived(credit.clone()));
+            }
+        }
+    }
+
     /// Fetch latest StoreCost for given number of bytes from the network.
     pub async fn get_store_cost(&self, bytes: u64) -> Result<(u64, Token, PublicKey), Error> {
         info!("Sending Query for latest StoreCost");
@@ -208,21 +346,30 @@ impl Client {
         &self,
         cmd: &DataCmd,
     ) -> Result<TransferAgreementProof, Error> {
-        info!("Sending requests for payment for write operation");
+        self.get_history().await?;
+
+        self.pay_for_write(cmd).await
+    }
 
+    /// Validates a transaction for paying the store_cost of a data command, the transfer
+    /// history of the local actor being expected to be in sync already
+    pub(crate) async fn pay_for_write(
+        &self,
+        cmd: &DataCmd,
+    ) -> Result<TransferAgreementProof, Error> {
         // Compute number of bytes
         let bytes = serialize(cmd)?.len() as u64;
 
-        self.get_history().await?;
+        info!("Sending requests for payment for write operation");
 
+        let actor = self.transfer_actor()?;
         let (bytes, cost_of_put, section_key) = self.get_store_cost(bytes).await?;
         info!(
             "Current store cost for {} bytes reported by section {}: {}",
             bytes, section_key, cost_of_put
         );
 
-        let initiated = self
-            .transfer_actor
+        let initiated = actor
             .read()
             .await
             .transfer(cost_of_put, section_key, "".to_string())?
@@ -237,7 +384,7 @@ impl Client {
 
         debug!("Transfer to be sent: {:?}", &signed_transfer);
 
-        self.transfer_actor
+        actor
             .write()
             .await
             .apply(ActorEvent::TransferInitiated(TransferInitiated {
@@ -262,16 +409,11 @@ impl Client {
 
         let (sender, mut receiver) = channel::<Result<TransferValidated, Error>>(7);
 
-        let client_pk = self.public_key();
-        let signature = self.keypair.sign(b"TODO");
-        let client_signed = ClientSigned {
-            public_key: client_pk,
-            signature,
-        };
+        let msg_id = MessageId::new();
+        let client_signed = sign_client_msg(&self.keypair, &cmd, &msg_id)?;
 
-        let msg_id = self
-            .session
-            .send_transfer_validation(cmd, client_signed, sender)
+        self.session
+            .send_transfer_validation(cmd, client_signed, msg_id, sender)
             .await?;
 
         let mut returned_errors = vec![];
@@ -283,7 +425,7 @@ impl Client {
                 Some(event) => match event {
                     Ok(transfer_validated) => {
                         response_count += 1;
-                        let mut actor = self.transfer_actor.write().await;
+                        let mut actor = self.transfer_actor()?.write().await;
                         // pass the received validation in to our actor
                         match actor.receive(transfer_validated) {
                             Ok(result) => {
@@ -408,4 +550,57 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    pub async fn transfer_history_holds_debits_and_credits() -> Result<()> {
+        let sender = create_test_client().await?;
+        let receiver = create_test_client().await?;
+        let amount = Token::from_str("1")?;
+
+        let _ = sender.send_tokens(receiver.public_key(), amount).await?;
+
+        let history = sender.get_transfer_history(sender.public_key(), 0).await?;
+        assert_eq!(history.debits.len(), 1);
+        assert_eq!(history.debits[0].amount, amount);
+        assert_eq!(history.debits[0].counterparty, receiver.public_key());
+        assert_eq!(history.version, 1);
+
+        let history = retry_loop_for_pattern!(
+            receiver.get_transfer_history(receiver.public_key(), 0),
+            Ok(history) if history.credits.len() > 1
+        )?;
+        assert!(history
+            .credits
+            .iter()
+            .any(|credit| credit.amount == amount && credit.counterparty == sender.public_key()));
+        assert!(history.debits.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn transfer_history_since_version_only_holds_new_debits() -> Result<()> {
+        let sender = create_test_client().await?;
+        let receiver = create_test_client().await?;
+        let amount = Token::from_str("1")?;
+
+        let _ = sender.send_tokens(receiver.public_key(), amount).await?;
+        let history = sender.get_transfer_history(sender.public_key(), 0).await?;
+
+        let _ = sender.send_tokens(receiver.public_key(), amount).await?;
+        let new_history = sender
+            .get_transfer_history(sender.public_key(), history.version)
+            .await?;
+        // only the debits are indexed, so the credits are returned again
+        assert_eq!(new_history.credits, history.credits);
+        assert_eq!(new_history.debits.len(), 1);
+        assert_eq!(new_history.debits[0].id.counter, 1);
+        assert_eq!(new_history.version, history.version + 1);
+
+        // the client's own actor is synced with both debits
+        let _ = sender.get_balance().await?;
+        assert_eq!(sender.get_local_balance().await, Token::from_str("8")?);
+
+        Ok(())
+    }
 }
@@ -58,6 +58,8 @@ impl Client {
     /// # Ok(())} );}
     /// ```
     pub async fn trigger_simulated_farming_payout(&mut self, amount: Token) -> Result<(), Error> {
+        self.check_writable()?;
+
         let pk = self.public_key();
         info!("Triggering a simulated farming payout to: {:?}", pk);
         self.simulated_farming_payout_dot.apply_inc();
@@ -0,0 +1,387 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Client;
+use crate::Error;
+use futures::stream::{self, Stream};
+use log::trace;
+use sn_data_types::{
+    register::{Address as RegisterAddress, Entry, EntryHash},
+    Sequence, SequenceAddress, SequenceEntry, SequenceIndex,
+};
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    time::Duration,
+};
+use tiny_keccak::{Hasher, Sha3};
+use tokio::time::sleep;
+
+// Time between polls of watched data right after it changed
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Time between polls of watched data which hasn't changed for a while
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Delay before polling watched data, growing for as long as the data doesn't change
+#[derive(Default)]
+struct Backoff {
+    delay: Option<Duration>,
+}
+
+impl Backoff {
+    // Wait before the next poll, the first one being immediate
+    async fn wait(&mut self) {
+        if let Some(delay) = self.delay {
+            sleep(delay).await;
+        }
+        self.delay = Some(self.delay.map_or(MIN_POLL_INTERVAL, |delay| {
+            (delay * 2).min(MAX_POLL_INTERVAL)
+        }));
+    }
+
+    // Poll again soon, as the data just changed
+    fn reset(&mut self) {
+        self.delay = Some(MIN_POLL_INTERVAL);
+    }
+}
+
+struct SequenceWatch {
+    client: Client,
+    address: SequenceAddress,
+    // How many times each entry, by hash, was yielded or held when the watch started, unknown
+    // until the Sequence was first fetched. Entries are told apart by value rather than by
+    // index, as merging the network's appends may move the local ones.
+    seen: Option<HashMap<[u8; 32], usize>>,
+    pending: VecDeque<(u64, SequenceEntry)>,
+    backoff: Backoff,
+}
+
+struct RegisterWatch {
+    client: Client,
+    address: RegisterAddress,
+    // Latest entries last yielded, unknown until the Register was first fetched
+    last: Option<BTreeSet<(EntryHash, Entry)>>,
+    backoff: Backoff,
+}
+
+impl Client {
+    /// Watch a Sequence for new entries, yielding each entry appended after the call along
+    /// with its index at the time it's yielded.
+    ///
+    /// The Sequence is polled, every second right after it changed and up to every 30 seconds
+    /// when it doesn't. The entries are read from the local replica of the Sequence once the
+    /// network's has been merged in, so appends made by this client but not yet sent are
+    /// yielded too. Errors fetching the Sequence are yielded as they happen, and polling goes
+    /// on afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use futures::StreamExt;
+    /// use sn_client::Client;
+    /// use sn_data_types::SequenceAddress;
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let address = SequenceAddress::Public { name: XorName::random(), tag: 10 };
+    /// let mut entries = client.watch_sequence(address);
+    /// while let Some(entry) = entries.next().await {
+    ///     let (index, value) = entry?;
+    ///     println!("Entry #{} appended: {:?}", index, value);
+    /// }
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn watch_sequence(
+        &self,
+        address: SequenceAddress,
+    ) -> impl Stream<Item = Result<(u64, SequenceEntry), Error>> + Unpin {
+        let seen = self
+            .get_local_sequence(address)
+            .and_then(|sequence| SequenceWatch::entries(&sequence).ok())
+            .map(|entries| count_entries(&entries));
+        let watch = SequenceWatch {
+            client: self.clone(),
+            address,
+            seen,
+            pending: VecDeque::new(),
+            backoff: Backoff::default(),
+        };
+
+        Box::pin(stream::unfold(watch, |mut watch| async move {
+            loop {
+                if let Some(entry) = watch.pending.pop_front() {
+                    return Some((Ok(entry), watch));
+                }
+
+                watch.backoff.wait().await;
+                trace!("Polling watched Sequence at {:?}", watch.address.name());
+                if let Err(error) = watch.poll_sequence().await {
+                    return Some((Err(error), watch));
+                }
+            }
+        }))
+    }
+
+    /// Watch a Register for changes, yielding its latest entries every time they change after
+    /// the call.
+    ///
+    /// The Register is polled, and its local replica used, as `watch_sequence` does for
+    /// Sequences.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tokio; use anyhow::Result;
+    /// # use sn_client::utils::test_utils::read_network_conn_info;
+    /// use futures::StreamExt;
+    /// use sn_client::Client;
+    /// use sn_data_types::register::Address;
+    /// use xor_name::XorName;
+    /// # #[tokio::main] async fn main() { let _: Result<()> = futures::executor::block_on( async {
+    /// # let bootstrap_contacts = Some(read_network_conn_info()?);
+    /// let client = Client::new(None, None, bootstrap_contacts).await?;
+    /// let address = Address::Public { name: XorName::random(), tag: 10 };
+    /// let mut changes = client.watch_register(address);
+    /// while let Some(latest) = changes.next().await {
+    ///     println!("Register changed, it now holds {} latest entries", latest?.len());
+    /// }
+    /// # Ok(()) } ); }
+    /// ```
+    pub fn watch_register(
+        &self,
+        address: RegisterAddress,
+    ) -> impl Stream<Item = Result<BTreeSet<(EntryHash, Entry)>, Error>> + Unpin {
+        let last = self
+            .get_local_register(address)
+            .and_then(|register| register.read(None).ok());
+        let watch = RegisterWatch {
+            client: self.clone(),
+            address,
+            last,
+            backoff: Backoff::default(),
+        };
+
+        Box::pin(stream::unfold(watch, |mut watch| async move {
+            loop {
+                watch.backoff.wait().await;
+                trace!("Polling watched Register at {:?}", watch.address.name());
+                match watch.poll_register().await {
+                    Ok(Some(latest)) => return Some((Ok(latest), watch)),
+                    Ok(None) => (),
+                    Err(error) => return Some((Err(error), watch)),
+                }
+            }
+        }))
+    }
+}
+
+impl SequenceWatch {
+    // Fetch the Sequence, queueing the entries appended since the last poll
+    async fn poll_sequence(&mut self) -> Result<(), Error> {
+        let sequence = self.client.fetch_and_merge_sequence(self.address).await?;
+        let entries = Self::entries(&sequence)?;
+        let seen = match self.seen.as_mut() {
+            Some(seen) => seen,
+            None => {
+                // Only the entries appended from now on are yielded
+                self.seen = Some(count_entries(&entries));
+                return Ok(());
+            }
+        };
+
+        // An entry is new once it's held more times than it was seen
+        let mut held = HashMap::new();
+        let mut appended = false;
+        for (index, entry) in (0..).zip(entries) {
+            let hash = entry_hash(&entry);
+            let count = held.entry(hash).or_insert(0);
+            *count += 1;
+            let seen_count = seen.entry(hash).or_default();
+            if *count > *seen_count {
+                *seen_count = *count;
+                self.pending.push_back((index, entry));
+                appended = true;
+            }
+        }
+        if appended {
+            self.backoff.reset();
+        }
+
+        Ok(())
+    }
+
+    fn entries(sequence: &Sequence) -> Result<Vec<SequenceEntry>, Error> {
+        if sequence.len(None)? == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(sequence
+            .in_range(SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0), None)?
+            .unwrap_or_default())
+    }
+}
+
+impl RegisterWatch {
+    // Fetch the Register, returning its latest entries if they changed since the last poll
+    async fn poll_register(&mut self) -> Result<Option<BTreeSet<(EntryHash, Entry)>>, Error> {
+        let register = self.client.fetch_and_merge_register(self.address).await?;
+        let latest = register.read(None)?;
+        let changed = self.last.as_ref().map(|last| *last != latest);
+        self.last = Some(latest.clone());
+
+        if changed == Some(true) {
+            self.backoff.reset();
+            Ok(Some(latest))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// How many times each entry is held, by hash
+fn count_entries(entries: &[SequenceEntry]) -> HashMap<[u8; 32], usize> {
+    let mut counts = HashMap::new();
+    for entry in entries {
+        *counts.entry(entry_hash(entry)).or_default() += 1;
+    }
+    counts
+}
+
+fn entry_hash(entry: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3::v256();
+    hasher.update(entry);
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{retry_loop, utils::test_utils::create_test_client};
+    use anyhow::{bail, Result};
+    use futures::StreamExt;
+    use sn_data_types::{
+        register::{PublicPermissions, User},
+        SequencePublicPermissions, SequenceUser,
+    };
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        time::Duration,
+    };
+    use tokio::time::timeout;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    pub async fn watched_sequence_yields_new_entries() -> Result<()> {
+        let client = create_test_client().await?;
+        let owner = client.public_key();
+        let mut perms = BTreeMap::<SequenceUser, SequencePublicPermissions>::new();
+        let _ = perms.insert(SequenceUser::Anyone, SequencePublicPermissions::new(true));
+        let address = client
+            .store_public_sequence(None, XorName::random(), 10, owner, perms)
+            .await?;
+        // synced first, so the watch starts from the Sequence as it is
+        retry_loop!(client.sync_sequence(address));
+        let mut entries = client.watch_sequence(address);
+
+        // appended by another client, so the entry only reaches the network
+        let other_client = create_test_client().await?;
+        let _ = retry_loop!(other_client.get_sequence(address));
+        retry_loop!(other_client.append_to_sequence(address, b"VALUE1".to_vec()));
+
+        match timeout(Duration::from_secs(10), entries.next()).await {
+            Ok(Some(Ok((index, entry)))) => {
+                assert_eq!(index, 0);
+                assert_eq!(entry, b"VALUE1".to_vec());
+            }
+            other => bail!("Unexpected watch result: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn watched_sequence_yields_each_entry_once_when_merged() -> Result<()> {
+        let client = create_test_client().await?;
+        let owner = client.public_key();
+        let mut perms = BTreeMap::<SequenceUser, SequencePublicPermissions>::new();
+        let _ = perms.insert(SequenceUser::Anyone, SequencePublicPermissions::new(true));
+        let address = client
+            .store_public_sequence(None, XorName::random(), 10, owner, perms)
+            .await?;
+        // synced first, so the watch starts from the Sequence as it is
+        retry_loop!(client.sync_sequence(address));
+        let mut entries = client.watch_sequence(address);
+
+        // queued locally, so it's yielded before the network holds it
+        client
+            .queue_sequence_append(address, b"LOCAL".to_vec())
+            .await?;
+        match timeout(Duration::from_secs(10), entries.next()).await {
+            Ok(Some(Ok((_, entry)))) => assert_eq!(entry, b"LOCAL".to_vec()),
+            other => bail!("Unexpected watch result: {:?}", other),
+        }
+
+        // the entry appended by another client may be merged in before the local one, which
+        // isn't yielded again
+        let other_client = create_test_client().await?;
+        let _ = retry_loop!(other_client.get_sequence(address));
+        retry_loop!(other_client.append_to_sequence(address, b"REMOTE".to_vec()));
+        match timeout(Duration::from_secs(10), entries.next()).await {
+            Ok(Some(Ok((_, entry)))) => assert_eq!(entry, b"REMOTE".to_vec()),
+            other => bail!("Unexpected watch result: {:?}", other),
+        }
+
+        let _ = client.flush_outbox().await?;
+        if let Ok(next) = timeout(Duration::from_secs(5), entries.next()).await {
+            bail!("Unexpected entry yielded again: {:?}", next);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn watched_register_yields_its_latest_entries() -> Result<()> {
+        let client = create_test_client().await?;
+        let owner = client.public_key();
+        let mut perms = BTreeMap::<User, PublicPermissions>::new();
+        let _ = perms.insert(User::Anyone, PublicPermissions::new(true));
+        let address = client
+            .store_public_register(XorName::random(), 10, owner, perms)
+            .await?;
+        // synced first, so the watch starts from the Register as it is
+        retry_loop!(client.sync_register(address));
+        let mut changes = client.watch_register(address);
+
+        // written by another client, so the entry only reaches the network
+        let other_client = create_test_client().await?;
+        let _ = retry_loop!(other_client.get_register(address));
+        let hash = retry_loop!(other_client.write_to_register(
+            address,
+            b"VALUE1".to_vec(),
+            BTreeSet::new()
+        ));
+
+        match timeout(Duration::from_secs(10), changes.next()).await {
+            Ok(Some(Ok(latest))) => {
+                assert_eq!(latest.len(), 1);
+                assert!(latest.contains(&(hash, b"VALUE1".to_vec())));
+            }
+            other => bail!("Unexpected watch result: {:?}", other),
+        }
+
+        // nothing is yielded while the Register doesn't change
+        if let Ok(next) = timeout(Duration::from_secs(3), changes.next()).await {
+            bail!("Unexpected change yielded: {:?}", next);
+        }
+
+        Ok(())
+    }
+}
@@ -10,71 +10,166 @@ use crate::Error;
 use log::{debug, warn};
 use qp2p::Config as QuicP2pConfig;
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashSet,
-    fs::File,
-    io::{self, BufReader},
-    net::SocketAddr,
-    path::Path,
-};
+use std::{collections::HashSet, fs, io, net::SocketAddr, path::Path, time::Duration};
 
 /// Configuration for sn_client.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
 pub struct Config {
     /// QuicP2p options.
     pub qp2p: QuicP2pConfig,
+    /// Timeouts and retry policy of the client.
+    #[serde(default)]
+    pub client: ClientConfig,
+}
+
+/// Timeouts and retry policy of the client. Fields missing from a config file take their
+/// default value.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Time to wait for elders to respond to a query, in seconds.
+    pub query_timeout_secs: u64,
+    /// Time to wait for a bootstrap contact to send the section info, in seconds, before
+    /// trying another one.
+    pub bootstrap_timeout_secs: u64,
+    /// Number of attempts to bootstrap to the network.
+    pub bootstrap_attempts: u8,
+    /// Number of times sending a message to an elder is retried after failing.
+    pub send_retries: usize,
+    /// Number of elders closest to the data a query is sent to.
+    pub elders_subset_for_queries: usize,
+    /// Time a command waits for elders to report an error, in milliseconds, before it's
+    /// deemed accepted. A command interrupted by a change of section info during that time is
+    /// sent again. Zero sends commands without waiting, nor sending them again.
+    pub cmd_error_wait_msec: u64,
+    /// Delay before the first retry, in milliseconds.
+    pub retry_initial_delay_msec: u64,
+    /// Maximum delay between retries, in milliseconds.
+    pub retry_max_delay_msec: u64,
+    /// Factor the delay grows by on each retry.
+    pub retry_backoff_factor: u32,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            query_timeout_secs: 3 * 60,
+            bootstrap_timeout_secs: 30,
+            bootstrap_attempts: 1,
+            send_retries: 3,
+            elders_subset_for_queries: 3,
+            cmd_error_wait_msec: 0,
+            retry_initial_delay_msec: 100,
+            retry_max_delay_msec: 5000,
+            retry_backoff_factor: 2,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Time to wait for elders to respond to a query.
+    pub fn query_timeout(&self) -> Duration {
+        Duration::from_secs(self.query_timeout_secs)
+    }
+
+    /// Time to wait for a bootstrap contact to send the section info.
+    pub fn bootstrap_timeout(&self) -> Duration {
+        Duration::from_secs(self.bootstrap_timeout_secs)
+    }
+
+    /// Time a command waits for elders to report an error.
+    pub fn cmd_error_wait(&self) -> Duration {
+        Duration::from_millis(self.cmd_error_wait_msec)
+    }
+
+    /// Delay before the given retry, counting from zero, growing exponentially up to the
+    /// maximum delay.
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        let delay = u64::from(self.retry_backoff_factor)
+            .checked_pow(retry)
+            .and_then(|factor| self.retry_initial_delay_msec.checked_mul(factor))
+            .unwrap_or(u64::MAX);
+        Duration::from_millis(delay.min(self.retry_max_delay_msec))
+    }
 }
 
 impl Config {
-    /// Returns a new `Config` instance. Tries to read quic-p2p config from file.
+    /// Returns a new `Config` instance. Tries to read the config from file, which may also
+    /// hold the quic-p2p config only, as config files used to, in which case the client config
+    /// takes its default value. The default config is used should the file fail to be read.
     pub fn new(
         config_file_path: Option<&Path>,
         bootstrap_config: Option<HashSet<SocketAddr>>,
     ) -> Self {
         // If a config file path was provided we try to read it,
-        // otherwise we use default qp2p config.
-        let mut qp2p = match &config_file_path {
-            None => QuicP2pConfig::default(),
+        // otherwise we use default config.
+        let mut config = match &config_file_path {
+            None => Config::default(),
             Some(path) => match read_config_file(path) {
-                Err(Error::IoError(ref err)) if err.kind() == io::ErrorKind::NotFound => {
-                    QuicP2pConfig {
+                Err(Error::IoError(ref err)) if err.kind() == io::ErrorKind::NotFound => Config {
+                    qp2p: QuicP2pConfig {
                         bootstrap_cache_dir: path.parent().map(|p| p.display().to_string()),
                         ..Default::default()
-                    }
+                    },
+                    ..Default::default()
+                },
+                Ok(config) => config,
+                Err(err) => {
+                    warn!(
+                        "Using the default config, as config file '{}' couldn't be read: {}",
+                        path.display(),
+                        err
+                    );
+                    Config::default()
                 }
-                result => result.unwrap_or_default(),
             },
         };
 
         if let Some(contacts) = bootstrap_config {
             debug!("Bootstrapping contacts overriden with: {:?}", contacts);
-            qp2p.hard_coded_contacts = contacts;
+            config.qp2p.hard_coded_contacts = contacts;
         }
 
-        Self { qp2p }
+        config
     }
 }
 
-fn read_config_file(filepath: &Path) -> Result<QuicP2pConfig, Error> {
-    match File::open(filepath) {
-        Ok(file) => {
-            debug!("Reading config file '{}' ...", filepath.display());
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).map_err(|err| {
-                warn!(
-                    "Could not parse content of config file '{}': {}",
-                    filepath.display(),
-                    err
-                );
-                err.into()
-            })
-        }
+fn read_config_file(filepath: &Path) -> Result<Config, Error> {
+    let content = match fs::read(filepath) {
+        Ok(content) => content,
         Err(err) => {
             warn!(
                 "Failed to open config file from '{}': {}",
                 filepath.display(),
                 err
             );
+            return Err(err.into());
+        }
+    };
+    debug!("Reading config file '{}' ...", filepath.display());
+
+    let err = match serde_json::from_slice(&content) {
+        Ok(config) => return Ok(config),
+        Err(err) => err,
+    };
+    // Config files used to hold the quic-p2p config only
+    match serde_json::from_slice(&content) {
+        Ok(qp2p) => {
+            warn!(
+                "Config file '{}' holds the quic-p2p config only, using the default client config",
+                filepath.display()
+            );
+            Ok(Config {
+                qp2p,
+                client: ClientConfig::default(),
+            })
+        }
+        Err(_) => {
+            warn!(
+                "Could not parse content of config file '{}': {}",
+                filepath.display(),
+                err
+            );
             Err(err.into())
         }
     }
@@ -86,7 +181,10 @@ mod tests {
     use crate::utils::test_utils::init_logger;
     use anyhow::Result;
     use rand::{distributions::Alphanumeric, thread_rng, Rng};
-    use std::{env::temp_dir, fs::create_dir_all};
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, File},
+    };
 
     // 1. Verify that `Config::new()` generates the correct default config
     //    when the file is not found. The default config shall have the provided
@@ -121,6 +219,7 @@ mod tests {
                 bootstrap_cache_dir: Some(str_path),
                 ..Default::default()
             },
+            ..Default::default()
         };
         assert_eq!(config, expected_config);
 
@@ -139,4 +238,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        let config = ClientConfig {
+            retry_initial_delay_msec: 100,
+            retry_max_delay_msec: 1000,
+            retry_backoff_factor: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(config.retry_delay(0), Duration::from_millis(100));
+        assert_eq!(config.retry_delay(1), Duration::from_millis(300));
+        assert_eq!(config.retry_delay(2), Duration::from_millis(900));
+        assert_eq!(config.retry_delay(3), Duration::from_millis(1000));
+        assert_eq!(config.retry_delay(u32::MAX), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn legacy_quic_p2p_config_file_is_read() -> Result<()> {
+        let random_filename: String = thread_rng().sample_iter(&Alphanumeric).take(15).collect();
+        let config_filepath = temp_dir().join(random_filename);
+        let qp2p = QuicP2pConfig {
+            hard_coded_contacts: vec!["127.0.0.1:12000".parse::<SocketAddr>()?]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        fs::write(&config_filepath, serde_json::to_vec(&qp2p)?)?;
+
+        let config = Config::new(Some(&config_filepath), None);
+        fs::remove_file(&config_filepath)?;
+        assert_eq!(
+            config,
+            Config {
+                qp2p,
+                client: ClientConfig::default(),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn client_config_defaults_when_missing() -> Result<()> {
+        let mut value = serde_json::to_value(Config::default())?;
+        value["client"] = serde_json::json!({ "send_retries": 0 });
+
+        let config: Config = serde_json::from_value(value)?;
+        assert_eq!(
+            config.client,
+            ClientConfig {
+                send_retries: 0,
+                ..Default::default()
+            }
+        );
+
+        Ok(())
+    }
 }
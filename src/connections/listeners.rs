@@ -6,10 +6,9 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::Session;
-use crate::Error;
+use super::{transport::IncomingMessages, Session};
+use crate::{client::ClientEvent, Error};
 use log::{debug, error, info, trace, warn};
-use qp2p::IncomingMessages;
 use sn_data_types::PublicKey;
 use sn_messaging::{
     client::{ClientMsg, Event, ProcessMsg},
@@ -30,6 +29,7 @@ impl Session {
         let _ = listeners
             .remove(msg_id)
             .ok_or(Error::NoTransferValidationListener)?;
+        self.in_flight.write().await.remove(msg_id);
 
         Ok(())
     }
@@ -66,7 +66,7 @@ impl Session {
         incoming_messages: &mut IncomingMessages,
         client_pk: PublicKey,
     ) -> Result<bool, Error> {
-        if let Some((src, message)) = incoming_messages.next().await {
+        if let Some((src, message)) = incoming_messages.recv().await {
             let message_type = WireMsg::deserialize(message)?;
             trace!("Incoming message from {:?}", &src);
             match message_type {
@@ -81,6 +81,7 @@ impl Session {
                         ClientMsg::ProcessingError(error) => {
                             warn!("Processing error received. {:?}", error);
                             // TODO: Handle lazy message errors
+                            self.notify(ClientEvent::ProcessingError(error));
                         }
                         msg => warn!("SupportingInfo received: {:?}", msg),
                     }
@@ -119,6 +120,7 @@ impl Session {
                     err
                 );
                 self.connect_to_elders().await?;
+                self.notify(ClientEvent::Reconnected);
                 Ok(())
             }
             SectionInfoMsg::GetSectionResponse(GetSectionResponse::SectionInfoUpdate(
@@ -133,28 +135,29 @@ impl Session {
                 // Disconnect from peer that sent us the redirect, connect to the new elders provided and
                 // request the section info again.
                 self.disconnect_from_peers(vec![src]).await?;
-                let endpoint = self.endpoint()?.clone();
                 let new_elders_addrs: Vec<SocketAddr> =
                     sap.elders.iter().map(|(_, addr)| *addr).collect();
-                self.qp2p
-                    .update_bootstrap_contacts(new_elders_addrs.as_slice());
+                self.transport
+                    .update_bootstrap_contacts(new_elders_addrs.as_slice())
+                    .await;
                 let boostrapped_peer = self
-                    .qp2p
-                    .rebootstrap(&endpoint, new_elders_addrs.as_slice())
+                    .transport
+                    .rebootstrap(new_elders_addrs.as_slice())
                     .await?;
                 self.send_get_section_query(client_pk, &boostrapped_peer)
                     .await?;
+                self.notify(ClientEvent::Reconnected);
 
                 Ok(())
             }
             SectionInfoMsg::SectionInfoUpdate(update) => {
                 let correlation_id = update.correlation_id;
-                error!("MessageId {:?} was interrupted due to infrastructure updates. This will most likely need to be sent again. Update was : {:?}", correlation_id, update);
+                warn!("MessageId {:?} was interrupted due to infrastructure updates, it will be sent again. Update was : {:?}", correlation_id, update);
                 if let SectionInfoError::TargetSectionInfoOutdated(sap) = update.clone().error {
                     trace!("Updated network info: ({:?})", sap);
                     self.update_session_info(&sap).await?;
                 }
-                Ok(())
+                self.resend_interrupted(correlation_id).await
             }
             SectionInfoMsg::GetSectionResponse(GetSectionResponse::SectionInfoUpdate(_))
             | SectionInfoMsg::GetSectionQuery { .. } => {
@@ -205,6 +208,12 @@ impl Session {
             *session_elders = received_elders.clone();
         }
 
+        self.notify(ClientEvent::SectionUpdated {
+            prefix: sap.prefix,
+            section_key: sap.public_key_set.public_key(),
+            elders: received_elders.values().copied().collect(),
+        });
+
         if original_known_elders != received_elders {
             debug!("Connecting to new set of Elders: {:?}", received_elders);
             let new_elder_addresses = received_elders.keys().cloned().collect::<BTreeSet<_>>();
@@ -220,7 +229,9 @@ impl Session {
                 })
                 .collect::<Vec<_>>();
             self.disconnect_from_peers(old_elders).await?;
-            self.qp2p.update_bootstrap_contacts(&updated_contacts);
+            self.transport
+                .update_bootstrap_contacts(&updated_contacts)
+                .await;
             self.connect_to_elders().await
         } else {
             Ok(())
@@ -236,7 +247,9 @@ impl Session {
         );
         let queries = self.pending_queries.clone();
         let transfers = self.pending_transfers.clone();
+        let cmds = self.pending_cmds.clone();
         let error_sender = self.incoming_err_sender.clone();
+        let events = self.events.clone();
         let _ = tokio::spawn(async move {
             debug!("Thread spawned to handle this client message");
             match msg {
@@ -258,7 +271,7 @@ impl Session {
                             "Sending response for query w/{} via channel.",
                             correlation_id
                         );
-                        let _ = sender.send(response).await;
+                        let _ = sender.send((src, response)).await;
                     } else {
                         trace!("No channel found for {:?}", correlation_id);
                     }
@@ -292,11 +305,29 @@ impl Session {
                     ..
                 } => {
                     debug!(
-                        "Cmd Error was received for Message w/ID: {:?}, sending on error channel",
+                        "Cmd Error was received for Message w/ID: {:?}",
                         correlation_id
                     );
                     trace!("Error received is: {:?}", error);
-                    let _ = error_sender.send(error).await;
+                    let _ = events.send(ClientEvent::CmdError {
+                        error: error.clone(),
+                        msg_id: correlation_id,
+                    });
+
+                    // Hand it to whoever awaits the outcome of the message, if anyone
+                    let cmd_sender = cmds.read().await.get(&correlation_id).cloned();
+                    let transfer_sender = transfers.read().await.get(&correlation_id).cloned();
+                    if let Some(sender) = cmd_sender {
+                        let _ = sender.send(error).await;
+                    } else if let Some(sender) = transfer_sender {
+                        let _ = sender.send(Err(Error::from((error, correlation_id)))).await;
+                    } else {
+                        trace!(
+                            "No listener for {:?}, sending on error channel",
+                            correlation_id
+                        );
+                        let _ = error_sender.send(error).await;
+                    }
                 }
                 msg => {
                     warn!("Ignoring unexpected message type received: {:?}", msg);
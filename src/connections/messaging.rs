@@ -6,31 +6,41 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{QueryResult, Session};
-use crate::Error;
+use super::{transport::Bootstrapped, InFlightMsg, QueryResult, Recipients, Session};
+use crate::{client::ClientEvent, Error};
+use bincode::serialize;
 use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
 use itertools::Itertools;
 use log::{debug, error, info, trace, warn};
-use sn_data_types::{Blob, PrivateBlob, PublicBlob, PublicKey, TransferValidated};
+use serde::Serialize;
+use sn_data_types::{Blob, PrivateBlob, PublicBlob, PublicKey, Signature, TransferValidated};
 use sn_messaging::{
-    client::{BlobRead, ClientMsg, ClientSigned, Cmd, DataQuery, ProcessMsg, Query, QueryResponse},
+    client::{
+        BlobRead, ClientMsg, ClientSigned, Cmd, CmdError, DataQuery, ProcessMsg, Query,
+        QueryResponse,
+    },
     section_info::SectionInfoMsg,
     MessageId,
 };
-use std::{collections::BTreeSet, net::SocketAddr, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    net::SocketAddr,
+    time::Duration,
+};
+use threshold_crypto::{PublicKey as BlsPublicKey, PublicKeySet};
 use tokio::{
-    sync::mpsc::{channel, Sender},
+    sync::mpsc::{channel, Receiver, Sender},
     task::JoinHandle,
-    time::timeout,
+    time::{sleep, timeout},
 };
 use xor_name::XorName;
 
-// Number of attemps when retrying to send a message to a node
-const NUMBER_OF_RETRIES: usize = 3;
-// Number of Elders subset to send queries to
-const NUM_OF_ELDERS_SUBSET_FOR_QUERIES: usize = 3;
-
-const QUERY_TIMEOUT_SECONDS: u64 = 3 * 60;
+/// The errors Elders may report for a command sent with `Session::dispatch_cmd`.
+pub(crate) struct CmdErrors {
+    msg_id: MessageId,
+    receiver: Receiver<CmdError>,
+}
 
 impl Session {
     /// Bootstrap to the network maintaining connections to several nodes.
@@ -40,21 +50,18 @@ impl Session {
             client_pk
         );
 
-        let (endpoint, _, mut incoming_messages, mut disconnections, mut bootstrapped_peer) =
-            self.qp2p.bootstrap().await?;
+        let Bootstrapped {
+            peer: mut bootstrapped_peer,
+            contacts,
+            mut incoming_messages,
+            mut disconnections,
+        } = self.transport.bootstrap().await?;
 
-        self.endpoint = Some(endpoint.clone());
-        let mut bootstrap_nodes = endpoint
-            .clone()
-            .bootstrap_nodes()
-            .to_vec()
-            .into_iter()
-            .collect::<BTreeSet<_>>();
-        // let connected_elders = self.connected_elders.clone();
+        let mut bootstrap_nodes = contacts.into_iter().collect::<BTreeSet<_>>();
 
-        let cloned_endpoint = endpoint.clone();
+        let session = self.clone();
         let _ = tokio::spawn(async move {
-            while let Some(disconnected_peer) = disconnections.next().await {
+            while let Some(disconnected_peer) = disconnections.recv().await {
                 // we assume elders should have high connectivity.
                 // any problem there and they'd be voted off and we'd get an updated section
                 // so just keep trying to reconnect
@@ -62,8 +69,11 @@ impl Session {
                     "Disconnected from elder {:?}. Attempting to reconnect",
                     disconnected_peer
                 );
-                match cloned_endpoint.connect_to(&disconnected_peer).await {
-                    Ok(_) => info!("Reconnected to {:?}", disconnected_peer),
+                match session.transport.connect_to(&disconnected_peer).await {
+                    Ok(_) => {
+                        info!("Reconnected to {:?}", disconnected_peer);
+                        session.notify(ClientEvent::Reconnected);
+                    }
                     Err(error) => {
                         warn!(
                             "Could not reconnect to {:?}, error: {:?}",
@@ -83,7 +93,7 @@ impl Session {
             // This means that the peer we bootstrapped to
             // has responded with a SectionInfo Message
             if let Ok(Ok(true)) = timeout(
-                Duration::from_secs(30),
+                self.config.bootstrap_timeout(),
                 self.process_incoming_message(&mut incoming_messages, client_pk),
             )
             .await
@@ -93,11 +103,8 @@ impl Session {
                 // Remove the unresponsive peer we boostrapped to and bootstrap again
                 let _ = bootstrap_nodes.remove(&bootstrapped_peer);
                 bootstrapped_peer = self
-                    .qp2p
-                    .rebootstrap(
-                        &endpoint,
-                        &bootstrap_nodes.iter().cloned().collect::<Vec<_>>(),
-                    )
+                    .transport
+                    .rebootstrap(&bootstrap_nodes.iter().cloned().collect::<Vec<_>>())
                     .await?;
             }
         }
@@ -114,9 +121,25 @@ impl Session {
     }
 
     /// Send a `ClientMsg` to the network without awaiting for a response.
-    pub async fn send_cmd(&self, cmd: Cmd, client_signed: ClientSigned) -> Result<(), Error> {
-        let msg_id = MessageId::new();
-        let endpoint = self.endpoint()?.clone();
+    pub async fn send_cmd(
+        &self,
+        cmd: Cmd,
+        client_signed: ClientSigned,
+        msg_id: MessageId,
+    ) -> Result<(), Error> {
+        let reported_errors = self.dispatch_cmd(cmd, client_signed, msg_id).await?;
+        self.await_cmd_errors(reported_errors).await
+    }
+
+    /// Send a `ClientMsg` to all Elders, returning once it's sent, along with the errors the
+    /// Elders may report for it, to be awaited with `await_cmd_errors`.
+    pub(crate) async fn dispatch_cmd(
+        &self,
+        cmd: Cmd,
+        client_signed: ClientSigned,
+        msg_id: MessageId,
+    ) -> Result<CmdErrors, Error> {
+        let transport = self.transport.clone();
 
         let elders: Vec<SocketAddr> = self.connected_elders.read().await.keys().cloned().collect();
         debug!(
@@ -125,7 +148,7 @@ impl Session {
             elders.len()
         );
 
-        let src_addr = endpoint.socket_addr();
+        let src_addr = transport.local_addr().await?;
         trace!(
             "Sending (from {}) command message {:?} w/ id: {:?}",
             src_addr,
@@ -148,17 +171,34 @@ impl Session {
 
         let msg_bytes = msg.serialize(dest_section_name, section_pk)?;
 
+        // Listen for errors reported by elders before sending, so none is missed. The command
+        // is only sent again if interrupted while waiting for those.
+        let (error_sender, error_receiver) = channel::<CmdError>(elders.len().max(1));
+        let cmd_error_wait = self.config.cmd_error_wait();
+        if cmd_error_wait > Duration::from_secs(0) {
+            let _ = self.pending_cmds.write().await.insert(msg_id, error_sender);
+            self.track_in_flight(
+                msg_id,
+                msg,
+                dest_section_name,
+                Recipients::AllElders,
+                section_pk,
+                msg_bytes.len(),
+            )
+            .await;
+        }
+
         // Send message to all Elders concurrently
         let mut tasks = Vec::default();
 
         // clone elders as we want to update them in this process
         for socket in elders {
             let msg_bytes_clone = msg_bytes.clone();
-            let endpoint = endpoint.clone();
+            let transport = transport.clone();
             let task_handle: JoinHandle<Result<(), Error>> = tokio::spawn(async move {
                 trace!("About to send cmd message {:?} to {:?}", msg_id, &socket);
-                endpoint.connect_to(&socket).await?;
-                endpoint.send_message(msg_bytes_clone, &socket).await?;
+                transport.connect_to(&socket).await?;
+                transport.send_message(msg_bytes_clone, &socket).await?;
 
                 trace!("Sent cmd with MsgId {:?}to {:?}", msg_id, &socket);
                 Ok(())
@@ -180,7 +220,37 @@ impl Session {
             error!("Sending the message to {} Elders failed", failures);
         }
 
-        Ok(())
+        Ok(CmdErrors {
+            msg_id,
+            receiver: error_receiver,
+        })
+    }
+
+    /// Wait for the errors reported for a command sent with `dispatch_cmd`, for as long as
+    /// the configured `cmd_error_wait`.
+    pub(crate) async fn await_cmd_errors(&self, reported_errors: CmdErrors) -> Result<(), Error> {
+        let cmd_error_wait = self.config.cmd_error_wait();
+        if cmd_error_wait == Duration::from_secs(0) {
+            return Ok(());
+        }
+
+        let CmdErrors {
+            msg_id,
+            receiver: mut error_receiver,
+        } = reported_errors;
+
+        // There is no acknowledgement of commands, so they are deemed accepted
+        // unless an elder reports an error within the wait
+        let reported = timeout(cmd_error_wait, error_receiver.recv()).await;
+        let _ = self.pending_cmds.write().await.remove(&msg_id);
+        self.in_flight.write().await.remove(&msg_id);
+        match reported {
+            Ok(Some(error)) => {
+                debug!("Command w/id {:?} was rejected: {:?}", msg_id, error);
+                Err(Error::from((error, msg_id)))
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Send a transfer validation message to all Elder without awaiting for a response.
@@ -188,14 +258,14 @@ impl Session {
         &self,
         cmd: Cmd,
         client_signed: ClientSigned,
+        msg_id: MessageId,
         sender: Sender<Result<TransferValidated, Error>>,
-    ) -> Result<MessageId, Error> {
-        let msg_id = MessageId::new();
+    ) -> Result<(), Error> {
         info!(
             "Sending transfer validation command {:?} w/ id: {:?}",
             cmd, msg_id
         );
-        let endpoint = self.endpoint()?.clone();
+        let transport = self.transport.clone();
         let elders: Vec<SocketAddr> = self.connected_elders.read().await.keys().cloned().collect();
         let pending_transfers = self.pending_transfers.clone();
 
@@ -212,6 +282,15 @@ impl Session {
             client_signed,
         });
         let msg_bytes = msg.serialize(dest_section_name, section_pk)?;
+        self.track_in_flight(
+            msg_id,
+            msg,
+            dest_section_name,
+            Recipients::AllElders,
+            section_pk,
+            msg_bytes.len(),
+        )
+        .await;
 
         let _ = pending_transfers.write().await.insert(msg_id, sender);
 
@@ -220,12 +299,12 @@ impl Session {
         for socket in elders.iter() {
             let msg_bytes_clone = msg_bytes.clone();
             let socket = *socket;
-            let endpoint = endpoint.clone();
+            let transport = transport.clone();
 
             let task_handle = tokio::spawn(async move {
-                endpoint.connect_to(&socket).await?;
+                transport.connect_to(&socket).await?;
                 trace!("Sending transfer validation to Elder {}", &socket);
-                endpoint.send_message(msg_bytes_clone, &socket).await?;
+                transport.send_message(msg_bytes_clone, &socket).await?;
                 Ok::<_, Error>(())
             });
             tasks.push(task_handle);
@@ -237,7 +316,7 @@ impl Session {
         // TODO: return an error if we didn't successfully
         // send it to at least a majority of Elders??
 
-        Ok(msg_id)
+        Ok(())
     }
 
     /// Send a Query `ClientMsg` to the network awaiting for the response.
@@ -245,10 +324,11 @@ impl Session {
         &self,
         query: Query,
         client_signed: ClientSigned,
+        msg_id: MessageId,
     ) -> Result<QueryResult, Error> {
         let data_name = query.dst_address();
 
-        let endpoint = self.endpoint()?.clone();
+        let transport = self.transport.clone();
         let pending_queries = self.pending_queries.clone();
 
         let chunk_addr = if let Query::Data(DataQuery::Blob(BlobRead::Get(address))) = query {
@@ -264,7 +344,6 @@ impl Session {
             .ok_or(Error::NoBlsSectionKey)?;
         let dest_section_name = XorName::from(client_signed.public_key);
 
-        let msg_id = MessageId::new();
         let msg = ClientMsg::Process(ProcessMsg::Query {
             id: msg_id,
             query,
@@ -272,26 +351,27 @@ impl Session {
         });
 
         let msg_bytes = msg.serialize(dest_section_name, section_pk)?;
-
-        // We select the NUM_OF_ELDERS_SUBSET_FOR_QUERIES closest
+        self.track_in_flight(
+            msg_id,
+            msg.clone(),
+            dest_section_name,
+            Recipients::ClosestTo(data_name),
+            section_pk,
+            msg_bytes.len(),
+        )
+        .await;
+
+        // We select the configured number of closest
         // connected Elders to the data we are querying
-        let elders: Vec<SocketAddr> = self
-            .connected_elders
-            .read()
-            .await
-            .clone()
-            .into_iter()
-            .sorted_by(|(_, lhs_name), (_, rhs_name)| data_name.cmp_distance(&lhs_name, &rhs_name))
-            .take(NUM_OF_ELDERS_SUBSET_FOR_QUERIES)
-            .map(|(addr, _)| addr)
-            .collect();
+        let elders = self.elders_closest_to(&data_name).await;
 
         let elders_len = elders.len();
-        if elders_len < NUM_OF_ELDERS_SUBSET_FOR_QUERIES {
+        if elders_len < self.config.elders_subset_for_queries {
             error!(
                 "Not enough Elder connections: {}, minimum required: {}",
-                elders_len, NUM_OF_ELDERS_SUBSET_FOR_QUERIES
+                elders_len, self.config.elders_subset_for_queries
             );
+            self.in_flight.write().await.remove(&msg_id);
             return Err(Error::InsufficientElderConnections(elders_len));
         }
 
@@ -302,7 +382,7 @@ impl Session {
 
         // We send the same message to all Elders concurrently
         let mut tasks = FuturesUnordered::new();
-        let (sender, mut receiver) = channel::<QueryResponse>(7);
+        let (sender, mut receiver) = channel::<(SocketAddr, QueryResponse)>(7);
 
         let pending_queries_for_thread = pending_queries.clone();
         let _ = tokio::spawn(async move {
@@ -316,17 +396,21 @@ impl Session {
 
         // Set up response listeners
         for socket in elders {
-            let endpoint = endpoint.clone();
+            let transport = transport.clone();
             let msg_bytes = msg_bytes.clone();
+            let config = self.config.clone();
             let task_handle = tokio::spawn(async move {
-                endpoint.connect_to(&socket).await?;
+                transport.connect_to(&socket).await?;
 
                 // Retry queries that failed due to connection issues only
                 let mut result = Err(Error::ElderQuery);
-                for attempt in 0..NUMBER_OF_RETRIES + 1 {
+                for attempt in 0..config.send_retries + 1 {
+                    if attempt > 0 {
+                        sleep(config.retry_delay(attempt as u32 - 1)).await;
+                    }
                     let msg_bytes_clone = msg_bytes.clone();
 
-                    if let Err(err) = endpoint.send_message(msg_bytes_clone, &socket).await {
+                    if let Err(err) = transport.send_message(msg_bytes_clone, &socket).await {
                         error!(
                             "Try #{:?} @ {:?}, failed sending query message: {:?}",
                             attempt + 1,
@@ -346,16 +430,13 @@ impl Session {
             tasks.push(task_handle);
         }
 
-        // TODO:
-        // We are now simply accepting the very first valid response we receive,
-        // but we may want to revisit this to compare multiple responses and validate them,
-        // similar to what we used to do up to the following commit:
-        // https://github.com/maidsafe/sn_client/blob/9091a4f1f20565f25d3a8b00571cc80751918928/src/connection_manager.rs#L328
+        // Unless a query response threshold has been set, we simply accept the very first
+        // valid response we receive.
         //
         // For Chunk responses we already validate its hash matches the xorname requested from,
         // so we don't need more than one valid response to prevent from accepting invaid responses
-        // from byzantine nodes, however for mutable data (non-Chunk esponses) we will
-        // have to review the approach.
+        // from byzantine nodes, however for mutable data (non-Chunk esponses) the threshold
+        // can be raised so that a number of elders have to agree on the response.
         let mut responses_discarded: usize = 0;
 
         // Send all queries first
@@ -373,12 +454,33 @@ impl Session {
             }
         }
 
+        if chunk_addr.is_none() && self.query_response_threshold > 1 {
+            let result = await_matching_responses(
+                &mut receiver,
+                msg_id,
+                self.query_response_threshold,
+                elders_len - responses_discarded,
+                section_pk,
+                self.config.query_timeout(),
+            )
+            .await;
+
+            self.in_flight.write().await.remove(&msg_id);
+            let _ = tokio::spawn(async move {
+                // Remove the response sender
+                trace!("Removing channel for {:?}", msg_id);
+                let _ = pending_queries.clone().write().await.remove(&msg_id);
+            });
+
+            return result.map(|response| QueryResult { response, msg_id });
+        }
+
         let response = loop {
             let mut error_response = None;
-            match (
-                timeout(Duration::from_secs(QUERY_TIMEOUT_SECONDS), receiver.recv()).await,
-                chunk_addr,
-            ) {
+            let received = timeout(self.config.query_timeout(), receiver.recv())
+                .await
+                .map(|received| received.map(|(_, response)| response));
+            match (received, chunk_addr) {
                 (Ok(Some(QueryResponse::GetBlob(Ok(blob)))), Some(chunk_addr)) => {
                     // We are dealing with Chunk query responses, thus we validate its hash
                     // matches its xorname, if so, we don't need to await for more responses
@@ -453,6 +555,7 @@ impl Session {
             msg_id, response
         );
 
+        self.in_flight.write().await.remove(&msg_id);
         let _ = tokio::spawn(async move {
             // Remove the response sender
             trace!("Removing channel for {:?}", msg_id);
@@ -464,6 +567,125 @@ impl Session {
             .ok_or(Error::NoResponse)
     }
 
+    /// Send again a message the elders let us know was interrupted, e.g. because it was sent
+    /// with outdated section info, once the section info has been updated.
+    ///
+    /// The message keeps its id, and so its signature and any listener awaiting responses to it.
+    /// It's only sent again if the section key changed since it was last sent.
+    pub(crate) async fn resend_interrupted(&self, msg_id: MessageId) -> Result<(), Error> {
+        let section_pk = self
+            .section_key()
+            .await?
+            .bls()
+            .ok_or(Error::NoBlsSectionKey)?;
+
+        let (msg_bytes, recipients) = {
+            let mut in_flight = self.in_flight.write().await;
+            let in_flight_msg = match in_flight.msgs.get_mut(&msg_id) {
+                Some(in_flight_msg) => in_flight_msg,
+                None => {
+                    warn!(
+                        "Interrupted message {:?} is no longer tracked, it can't be sent again",
+                        msg_id
+                    );
+                    return Ok(());
+                }
+            };
+            if in_flight_msg.section_pk == section_pk {
+                trace!(
+                    "Message {:?} was already sent with the latest section info",
+                    msg_id
+                );
+                return Ok(());
+            }
+            in_flight_msg.section_pk = section_pk;
+            let msg_bytes = in_flight_msg
+                .msg
+                .serialize(in_flight_msg.dest_section_name, section_pk)?;
+            (msg_bytes, in_flight_msg.recipients)
+        };
+
+        let elders = match recipients {
+            Recipients::AllElders => self.connected_elders.read().await.keys().cloned().collect(),
+            Recipients::ClosestTo(name) => self.elders_closest_to(&name).await,
+        };
+        info!(
+            "Sending interrupted message {:?} again to {} Elders",
+            msg_id,
+            elders.len()
+        );
+
+        let tasks = elders.into_iter().map(|socket| {
+            let transport = self.transport.clone();
+            let msg_bytes = msg_bytes.clone();
+            async move {
+                transport.connect_to(&socket).await?;
+                transport.send_message(msg_bytes, &socket).await
+            }
+        });
+        let failures = join_all(tasks)
+            .await
+            .into_iter()
+            .filter(Result::is_err)
+            .count();
+        if failures > 0 {
+            error!(
+                "Sending message {:?} again to {} Elders failed",
+                msg_id, failures
+            );
+        }
+
+        Ok(())
+    }
+
+    // Keep track of a message sent, so it can be sent again if interrupted
+    async fn track_in_flight(
+        &self,
+        msg_id: MessageId,
+        msg: ClientMsg,
+        dest_section_name: XorName,
+        recipients: Recipients,
+        section_pk: threshold_crypto::PublicKey,
+        size: usize,
+    ) {
+        self.in_flight.write().await.insert(
+            msg_id,
+            InFlightMsg {
+                msg,
+                dest_section_name,
+                recipients,
+                section_pk,
+                size,
+            },
+        );
+    }
+
+    // The configured number of connected Elders closest to the given name
+    async fn elders_closest_to(&self, name: &XorName) -> Vec<SocketAddr> {
+        self.connected_elders
+            .read()
+            .await
+            .clone()
+            .into_iter()
+            .sorted_by(|(_, lhs_name), (_, rhs_name)| name.cmp_distance(&lhs_name, &rhs_name))
+            .take(self.config.elders_subset_for_queries)
+            .map(|(addr, _)| addr)
+            .collect()
+    }
+
+    /// Set how long commands wait for elders to report an error before being deemed accepted,
+    /// not waiting at all if zero.
+    pub(crate) fn set_cmd_error_wait(&mut self, wait: Duration) {
+        self.config.cmd_error_wait_msec = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+    }
+
+    /// Set the number of elders which must return matching responses to a non-Chunk query
+    /// before accepting it, capped to the number of elders each query is sent to.
+    pub(crate) fn set_query_response_threshold(&mut self, threshold: usize) {
+        self.query_response_threshold =
+            threshold.clamp(1, self.config.elders_subset_for_queries.max(1));
+    }
+
     // Get section info from the peer we have bootstrapped with.
     pub(crate) async fn send_get_section_query(
         &self,
@@ -489,16 +711,14 @@ impl Session {
         let msg = SectionInfoMsg::GetSectionQuery(client_pk)
             .serialize(dest_section_name, random_section_pk)?;
 
-        self.endpoint()?
-            .send_message(msg, bootstrapped_peer)
-            .await?;
+        self.transport.send_message(msg, bootstrapped_peer).await?;
 
         Ok(())
     }
 
     pub(crate) async fn disconnect_from_peers(&self, peers: Vec<SocketAddr>) -> Result<(), Error> {
         for elder in peers {
-            self.endpoint()?.disconnect_from(&elder).await?;
+            self.transport.disconnect_from(&elder).await?;
         }
 
         Ok(())
@@ -530,3 +750,286 @@ impl Session {
         Ok(())
     }
 }
+
+// Wait for the given number of elders to return matching responses to a query, or for a
+// response signed by the section, as none of its elders can make one up. Should not enough
+// of them agree, the elders which dissented from the response most of them returned are
+// reported, or all those which responded if none was returned by more elders than any other.
+async fn await_matching_responses(
+    receiver: &mut Receiver<(SocketAddr, QueryResponse)>,
+    msg_id: MessageId,
+    threshold: usize,
+    expected_responses: usize,
+    section_pk: BlsPublicKey,
+    query_timeout: Duration,
+) -> Result<QueryResponse, Error> {
+    // Elders which sent each distinct response, keyed by the serialised response
+    let mut responses: HashMap<Vec<u8>, BTreeSet<SocketAddr>> = HashMap::new();
+    let mut responders = BTreeSet::new();
+
+    while responders.len() < expected_responses {
+        let (src, response) = match timeout(query_timeout, receiver.recv()).await {
+            Ok(Some(received)) => received,
+            Ok(None) => {
+                debug!("QueryResponse channel closed.");
+                break;
+            }
+            Err(error) => {
+                error!(
+                    "Timeout while waiting for response to client request w/ id {:?}: {:?}",
+                    &msg_id, &error
+                );
+                break;
+            }
+        };
+
+        if !responders.insert(src) {
+            warn!("Ignoring repeated response to {:?} from {}", msg_id, src);
+            continue;
+        }
+
+        if is_signed_by_section(&response, &section_pk) {
+            debug!(
+                "Response to {:?} from {} is signed by the section",
+                msg_id, src
+            );
+            return Ok(response);
+        }
+
+        let elders = responses.entry(serialize(&response)?).or_default();
+        let _ = elders.insert(src);
+        if elders.len() >= threshold {
+            debug!(
+                "{} elders agreed on the response to {:?}: {:?}",
+                elders.len(),
+                msg_id,
+                elders
+            );
+            return Ok(response);
+        }
+    }
+
+    if responses.len() > 1 {
+        warn!(
+            "Elders returned {} different responses to {:?}",
+            responses.len(),
+            msg_id
+        );
+        let largest = responses.values().map(BTreeSet::len).max().unwrap_or(0);
+        let mut largest_groups = responses.values().filter(|elders| elders.len() == largest);
+        let elders = match (largest_groups.next(), largest_groups.next()) {
+            (Some(majority), None) => responders.difference(majority).copied().collect(),
+            _ => responders.into_iter().collect(),
+        };
+        Err(Error::ConflictingQueryResponses { elders, msg_id })
+    } else {
+        Err(Error::NoResponse)
+    }
+}
+
+// Whether the response holds transfer proofs only, all of them signed by the section, so it
+// couldn't have been made up by any of its elders
+fn is_signed_by_section(response: &QueryResponse, section_pk: &BlsPublicKey) -> bool {
+    let history = match response {
+        QueryResponse::GetHistory(Ok(history)) => history,
+        _ => return false,
+    };
+    if history.credits.is_empty() && history.debits.is_empty() {
+        return false;
+    }
+
+    history.credits.iter().all(|proof| {
+        signed_by_section(
+            &proof.signed_credit,
+            &proof.debiting_replicas_sig,
+            &proof.debiting_replicas_keys,
+            section_pk,
+        )
+    }) && history.debits.iter().all(|proof| {
+        signed_by_section(
+            &proof.signed_debit,
+            &proof.debit_sig,
+            &proof.debiting_replicas_keys,
+            section_pk,
+        ) && signed_by_section(
+            &proof.signed_credit,
+            &proof.credit_sig,
+            &proof.debiting_replicas_keys,
+            section_pk,
+        )
+    })
+}
+
+// Whether the data was signed with the key of the section
+fn signed_by_section<T: Serialize>(
+    data: &T,
+    signature: &Signature,
+    keys: &PublicKeySet,
+    section_pk: &BlsPublicKey,
+) -> bool {
+    let key = keys.public_key();
+    if key != *section_pk {
+        return false;
+    }
+
+    match serialize(data) {
+        Ok(bytes) => PublicKey::Bls(key).verify(signature, &bytes).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use crdts::Dot;
+    use rand::rngs::OsRng;
+    use sn_data_types::{ActorHistory, Credit, CreditAgreementProof, Keypair, SignedCredit, Token};
+    use sn_messaging::client::Error as ErrorMessage;
+    use std::net::{IpAddr, Ipv4Addr};
+    use threshold_crypto::{SecretKey, SecretKeySet};
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn elder(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    fn balance(nanos: u64) -> QueryResponse {
+        QueryResponse::GetBalance(Ok(Token::from_nano(nanos)))
+    }
+
+    fn section_pk() -> BlsPublicKey {
+        SecretKey::random().public_key()
+    }
+
+    // A history holding a credit signed with the given key set
+    fn signed_history(secret_key_set: &SecretKeySet) -> Result<QueryResponse> {
+        let sender = Keypair::new_ed25519(&mut OsRng);
+        let credit = Credit {
+            id: Dot::new(sender.public_key(), 0),
+            amount: Token::from_nano(10),
+            recipient: Keypair::new_ed25519(&mut OsRng).public_key(),
+            msg: "".to_string(),
+        };
+        let signed_credit = SignedCredit {
+            actor_signature: sender.sign(&serialize(&credit)?),
+            credit,
+        };
+        let debiting_replicas_sig = Signature::Bls(
+            secret_key_set
+                .secret_key()
+                .sign(&serialize(&signed_credit)?),
+        );
+        Ok(QueryResponse::GetHistory(Ok(ActorHistory {
+            credits: vec![CreditAgreementProof {
+                signed_credit,
+                debiting_replicas_sig,
+                debiting_replicas_keys: secret_key_set.public_keys(),
+            }],
+            debits: vec![],
+        })))
+    }
+
+    #[tokio::test]
+    async fn matching_responses_reach_threshold() -> Result<()> {
+        let (sender, mut receiver) = channel(7);
+        sender.send((elder(1), balance(10))).await?;
+        sender.send((elder(2), balance(20))).await?;
+        sender.send((elder(3), balance(10))).await?;
+
+        match await_matching_responses(&mut receiver, MessageId::new(), 2, 3, section_pk(), TIMEOUT)
+            .await?
+        {
+            QueryResponse::GetBalance(Ok(token)) => assert_eq!(token, Token::from_nano(10)),
+            response => return Err(anyhow!("Unexpected response: {:?}", response)),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repeated_responses_from_an_elder_are_counted_once() -> Result<()> {
+        let (sender, mut receiver) = channel(7);
+        sender.send((elder(1), balance(10))).await?;
+        sender.send((elder(1), balance(10))).await?;
+        drop(sender);
+
+        match await_matching_responses(&mut receiver, MessageId::new(), 2, 3, section_pk(), TIMEOUT)
+            .await
+        {
+            Err(Error::NoResponse) => Ok(()),
+            result => Err(anyhow!("Unexpected result: {:?}", result)),
+        }
+    }
+
+    #[tokio::test]
+    async fn conflicting_responses_list_the_elders() -> Result<()> {
+        let (sender, mut receiver) = channel(7);
+        sender.send((elder(1), balance(10))).await?;
+        sender.send((elder(2), balance(20))).await?;
+        sender
+            .send((
+                elder(3),
+                QueryResponse::GetBalance(Err(ErrorMessage::NoSuchEntry)),
+            ))
+            .await?;
+
+        match await_matching_responses(&mut receiver, MessageId::new(), 2, 3, section_pk(), TIMEOUT)
+            .await
+        {
+            Err(Error::ConflictingQueryResponses { elders, .. }) => {
+                assert_eq!(elders, vec![elder(1), elder(2), elder(3)]);
+                Ok(())
+            }
+            result => Err(anyhow!("Unexpected result: {:?}", result)),
+        }
+    }
+    #[tokio::test]
+    async fn conflicting_responses_list_the_dissenting_elders() -> Result<()> {
+        let (sender, mut receiver) = channel(7);
+        sender.send((elder(1), balance(10))).await?;
+        sender.send((elder(2), balance(20))).await?;
+        sender.send((elder(3), balance(10))).await?;
+
+        match await_matching_responses(&mut receiver, MessageId::new(), 3, 3, section_pk(), TIMEOUT)
+            .await
+        {
+            Err(Error::ConflictingQueryResponses { elders, .. }) => {
+                assert_eq!(elders, vec![elder(2)]);
+                Ok(())
+            }
+            result => Err(anyhow!("Unexpected result: {:?}", result)),
+        }
+    }
+
+    #[tokio::test]
+    async fn response_signed_by_the_section_is_enough() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(1, &mut rand::thread_rng());
+        let section_pk = secret_key_set.public_keys().public_key();
+        let (sender, mut receiver) = channel(7);
+
+        // signed by another section, it has to be matched by other elders
+        let other_section_keys = SecretKeySet::random(1, &mut rand::thread_rng());
+        let response = signed_history(&other_section_keys)?;
+        sender.send((elder(1), response)).await?;
+        sender.send((elder(2), balance(10))).await?;
+        drop(sender);
+        match await_matching_responses(&mut receiver, MessageId::new(), 2, 3, section_pk, TIMEOUT)
+            .await
+        {
+            Err(Error::ConflictingQueryResponses { .. }) => (),
+            result => return Err(anyhow!("Unexpected result: {:?}", result)),
+        }
+
+        let (sender, mut receiver) = channel(7);
+        let response = signed_history(&secret_key_set)?;
+        sender.send((elder(1), response.clone())).await?;
+        let received =
+            await_matching_responses(&mut receiver, MessageId::new(), 2, 3, section_pk, TIMEOUT)
+                .await?;
+        assert_eq!(serialize(&received)?, serialize(&response)?);
+
+        Ok(())
+    }
+}
@@ -8,43 +8,121 @@
 
 mod listeners;
 mod messaging;
+pub mod transport;
 
-use crate::Error;
-use log::{debug, trace};
-use qp2p::{Config as QuicP2pConfig, Endpoint, QuicP2p};
+pub(crate) use self::messaging::CmdErrors;
+use self::transport::Transport;
+use crate::{client::ClientEvent, config_handler::ClientConfig, Error};
+use log::trace;
 use sn_data_types::{PublicKey, TransferValidated};
 use sn_messaging::client::CmdError;
-use sn_messaging::{client::QueryResponse, MessageId};
+use sn_messaging::{
+    client::{ClientMsg, QueryResponse},
+    MessageId,
+};
 use std::{
     borrow::Borrow,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     net::SocketAddr,
     sync::Arc,
 };
-use threshold_crypto::PublicKeySet;
-use tokio::sync::mpsc::Sender;
+use threshold_crypto::{PublicKey as BlsPublicKey, PublicKeySet};
 use tokio::sync::RwLock;
+use tokio::sync::{
+    broadcast::{self, Receiver as EventReceiver},
+    mpsc::Sender,
+};
 use xor_name::{Prefix, XorName};
 
 // Channel for sending result of transfer validation
 type TransferValidationSender = Sender<Result<TransferValidated, Error>>;
-type QueryResponseSender = Sender<QueryResponse>;
+type QueryResponseSender = Sender<(SocketAddr, QueryResponse)>;
 
 type PendingTransferValidations = Arc<RwLock<HashMap<MessageId, TransferValidationSender>>>;
+type PendingCmdErrors = Arc<RwLock<HashMap<MessageId, Sender<CmdError>>>>;
 type PendingQueryResponses = Arc<RwLock<HashMap<MessageId, QueryResponseSender>>>;
 
+// Number of events kept for subscribers which fell behind
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+// Number of messages sent which are kept track of, so they can be sent again if interrupted
+const IN_FLIGHT_CAPACITY: usize = 1024;
+
+// Number of bytes of the messages sent which are kept track of, as commands storing chunks
+// are as big as those
+const IN_FLIGHT_CAPACITY_BYTES: usize = 16 * 1024 * 1024;
+
 pub(crate) struct QueryResult {
     pub response: QueryResponse,
     pub msg_id: MessageId,
 }
 
+// Elders a message is sent to
+#[derive(Clone, Copy, Debug)]
+enum Recipients {
+    AllElders,
+    ClosestTo(XorName),
+}
+
+// A message sent to the elders, along with what's needed to send it again
+struct InFlightMsg {
+    msg: ClientMsg,
+    dest_section_name: XorName,
+    recipients: Recipients,
+    // Section key the message was last serialised for
+    section_pk: BlsPublicKey,
+    // Number of bytes of the message once serialised
+    size: usize,
+}
+
+// Messages which may need to be sent again, should elders let us know they were sent with
+// outdated section info. Queries and transfer validations are removed when complete, and
+// commands once the wait for errors reported on them is over. Should more messages than
+// capacity be in flight nonetheless, in number or in bytes, the oldest ones are dropped.
+#[derive(Default)]
+struct InFlightMessages {
+    msgs: HashMap<MessageId, InFlightMsg>,
+    order: VecDeque<MessageId>,
+    // Number of bytes of all the messages held
+    bytes: usize,
+}
+
+impl InFlightMessages {
+    fn insert(&mut self, msg_id: MessageId, msg: InFlightMsg) {
+        self.bytes += msg.size;
+        match self.msgs.insert(msg_id, msg) {
+            Some(replaced) => self.bytes -= replaced.size,
+            None => self.order.push_back(msg_id),
+        }
+        while self.order.len() > IN_FLIGHT_CAPACITY || self.bytes > IN_FLIGHT_CAPACITY_BYTES {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    if let Some(dropped) = self.msgs.remove(&oldest) {
+                        self.bytes -= dropped.size;
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, msg_id: &MessageId) {
+        if let Some(removed) = self.msgs.remove(msg_id) {
+            self.bytes -= removed.size;
+            self.order.retain(|id| id != msg_id);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Session {
-    qp2p: QuicP2p,
+    transport: Arc<dyn Transport>,
     pending_queries: PendingQueryResponses,
     pending_transfers: PendingTransferValidations,
+    pending_cmds: PendingCmdErrors,
+    in_flight: Arc<RwLock<InFlightMessages>>,
     incoming_err_sender: Arc<Sender<CmdError>>,
-    endpoint: Option<Endpoint>,
+    events: broadcast::Sender<ClientEvent>,
     /// elders we've managed to connect to
     connected_elders: Arc<RwLock<BTreeMap<SocketAddr, XorName>>>,
     /// all elders we know about from SectionInfo messages
@@ -52,25 +130,51 @@ pub struct Session {
     pub section_key_set: Arc<RwLock<Option<PublicKeySet>>>,
     section_prefix: Arc<RwLock<Option<Prefix>>>,
     is_connecting_to_new_elders: bool,
+    /// number of elders which must return matching responses to a query
+    query_response_threshold: usize,
+    /// timeouts and retry policy
+    config: ClientConfig,
 }
 
 impl Session {
-    pub fn new(qp2p_config: QuicP2pConfig, err_sender: Sender<CmdError>) -> Result<Self, Error> {
-        debug!("QP2p config: {:?}", qp2p_config);
-
-        let qp2p = qp2p::QuicP2p::with_config(Some(qp2p_config), Default::default(), true)?;
-        Ok(Self {
-            qp2p,
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        err_sender: Sender<CmdError>,
+        config: ClientConfig,
+    ) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            transport,
             pending_queries: Arc::new(RwLock::new(HashMap::default())),
             pending_transfers: Arc::new(RwLock::new(HashMap::default())),
+            pending_cmds: Arc::new(RwLock::new(HashMap::default())),
+            in_flight: Arc::new(RwLock::new(InFlightMessages::default())),
             incoming_err_sender: Arc::new(err_sender),
-            endpoint: None,
+            events,
             section_key_set: Arc::new(RwLock::new(None)),
             connected_elders: Arc::new(RwLock::new(Default::default())),
             all_known_elders: Arc::new(RwLock::new(Default::default())),
             section_prefix: Arc::new(RwLock::new(None)),
             is_connecting_to_new_elders: false,
-        })
+            query_response_threshold: 1,
+            config,
+        }
+    }
+
+    /// Timeouts and retry policy of this session
+    pub(crate) fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Subscribe to the events of this session
+    pub(crate) fn subscribe(&self) -> EventReceiver<ClientEvent> {
+        self.events.subscribe()
+    }
+
+    /// Let the subscribers, if any, know about an event
+    pub(crate) fn notify(&self, event: ClientEvent) {
+        trace!("Notifying event: {:?}", event);
+        let _ = self.events.send(event);
     }
 
     /// Get the SuperMajority count based on number of known elders
@@ -88,16 +192,6 @@ impl Session {
         self.all_known_elders.read().await.len()
     }
 
-    pub fn endpoint(&self) -> Result<&Endpoint, Error> {
-        match self.endpoint.borrow() {
-            Some(endpoint) => Ok(endpoint),
-            None => {
-                trace!("self.endpoint.borrow() was None");
-                Err(Error::NotBootstrapped)
-            }
-        }
-    }
-
     pub async fn section_key(&self) -> Result<PublicKey, Error> {
         let keys = self.section_key_set.read().await.clone();
 
@@ -115,3 +209,105 @@ impl Session {
         *self.section_prefix.read().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::sign_client_msg;
+    #[cfg(feature = "mock-network")]
+    use crate::utils::test_utils::{create_test_client, MockNetwork};
+    use anyhow::Result;
+    use rand::rngs::OsRng;
+    use sn_data_types::Keypair;
+    use sn_messaging::client::{ProcessMsg, Query, TransferQuery};
+
+    fn in_flight_query(section_pk: BlsPublicKey) -> Result<(MessageId, InFlightMsg)> {
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let query = Query::Transfer(TransferQuery::GetBalance(keypair.public_key()));
+        let id = MessageId::new();
+        let client_signed = sign_client_msg(&keypair, &query, &id)?;
+        let msg = ClientMsg::Process(ProcessMsg::Query {
+            id,
+            query,
+            client_signed,
+        });
+        let dest_section_name = XorName::random();
+        let size = msg.serialize(dest_section_name, section_pk)?.len();
+        let msg = InFlightMsg {
+            msg,
+            dest_section_name,
+            recipients: Recipients::AllElders,
+            section_pk,
+            size,
+        };
+        Ok((id, msg))
+    }
+
+    #[test]
+    fn in_flight_messages_are_capped() -> Result<()> {
+        let section_pk = threshold_crypto::SecretKey::random().public_key();
+        let mut in_flight = InFlightMessages::default();
+
+        let mut ids = Vec::new();
+        for _ in 0..IN_FLIGHT_CAPACITY + 1 {
+            let (id, msg) = in_flight_query(section_pk)?;
+            in_flight.insert(id, msg);
+            ids.push(id);
+        }
+
+        // the oldest message is no longer tracked
+        assert_eq!(in_flight.msgs.len(), IN_FLIGHT_CAPACITY);
+        assert!(!in_flight.msgs.contains_key(&ids[0]));
+        assert!(in_flight.msgs.contains_key(&ids[1]));
+
+        in_flight.remove(&ids[1]);
+        assert!(!in_flight.msgs.contains_key(&ids[1]));
+        assert_eq!(in_flight.order.len(), IN_FLIGHT_CAPACITY - 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_flight_messages_are_capped_in_bytes() -> Result<()> {
+        let section_pk = threshold_crypto::SecretKey::random().public_key();
+        let mut in_flight = InFlightMessages::default();
+
+        // messages as big as a chunk
+        let big_size = 1024 * 1024;
+        let mut ids = Vec::new();
+        for _ in 0..IN_FLIGHT_CAPACITY_BYTES / big_size + 1 {
+            let (id, mut msg) = in_flight_query(section_pk)?;
+            msg.size = big_size;
+            in_flight.insert(id, msg);
+            ids.push(id);
+        }
+
+        // the oldest message is no longer tracked, though far fewer than capacity in number
+        assert!(in_flight.bytes <= IN_FLIGHT_CAPACITY_BYTES);
+        assert!(!in_flight.msgs.contains_key(&ids[0]));
+        assert!(in_flight.msgs.contains_key(&ids[1]));
+
+        in_flight.remove(&ids[1]);
+        assert_eq!(in_flight.bytes, (ids.len() - 2) * big_size);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mock-network")]
+    #[tokio::test]
+    async fn interrupted_query_is_sent_again_with_the_same_id() -> Result<()> {
+        let client = create_test_client().await?;
+        let network = MockNetwork::shared().await?;
+
+        // the query is sent with the section info the client has before the churn,
+        // so the elders reject it with the new one
+        network.churn().await;
+        let query = Query::Transfer(TransferQuery::GetBalance(client.public_key()));
+        let result = client.send_query(query).await?;
+
+        assert!(matches!(result.response, QueryResponse::GetBalance(Ok(_))));
+        assert!(network.interrupted_msgs().await.contains(&result.msg_id));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Bootstrapped, Disconnections, IncomingMessages, Transport};
+use crate::Error;
+use async_trait::async_trait;
+use bytes::Bytes;
+use log::trace;
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::{
+    mpsc::{channel, Sender},
+    RwLock,
+};
+
+// Number of messages buffered for each transport before applying backpressure
+const CHANNEL_CAPACITY: usize = 1024;
+
+type MessageSender = Sender<(SocketAddr, Bytes)>;
+
+// Where a peer is sent messages, and told of the peers it got disconnected from
+#[derive(Clone)]
+struct PeerSenders {
+    messages: MessageSender,
+    disconnections: Sender<SocketAddr>,
+}
+
+/// An in-memory network, connecting the `LoopbackTransport`s created from it.
+///
+/// Messages are handed over through channels, which makes it suitable for tests and for
+/// embedding a client in the same process as the nodes it talks to.
+#[derive(Clone, Default)]
+pub struct LoopbackNetwork {
+    peers: Arc<Mutex<HashMap<SocketAddr, PeerSenders>>>,
+    next_port: Arc<AtomicU16>,
+}
+
+impl LoopbackNetwork {
+    /// Create an empty loopback network.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a new peer to the network, which will bootstrap to the first reachable contact
+    /// provided. Each peer gets a distinct address in the network.
+    pub fn transport(&self, contacts: &[SocketAddr]) -> LoopbackTransport {
+        let port = self.next_port.fetch_add(1, Ordering::Relaxed) + 1;
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let (message_sender, incoming_messages) = channel(CHANNEL_CAPACITY);
+        let (disconnection_sender, disconnections) = channel(CHANNEL_CAPACITY);
+
+        if let Ok(mut peers) = self.peers.lock() {
+            let senders = PeerSenders {
+                messages: message_sender,
+                disconnections: disconnection_sender,
+            };
+            let _ = peers.insert(addr, senders);
+        }
+        trace!("Loopback peer added at {}", addr);
+
+        LoopbackTransport {
+            network: self.clone(),
+            addr,
+            contacts: RwLock::new(contacts.to_vec()),
+            streams: RwLock::new(Some((incoming_messages, disconnections))),
+        }
+    }
+
+    /// Tell every other peer that its connection to the given one was lost, as a transport
+    /// would when the connection drops. The peer stays reachable, so they can reconnect.
+    pub fn disconnect_peers_from(&self, peer: &SocketAddr) {
+        let peers = match self.peers.lock() {
+            Ok(peers) => peers,
+            Err(_) => return,
+        };
+        for (addr, senders) in peers.iter().filter(|(addr, _)| *addr != peer) {
+            trace!("Loopback peer {} disconnected from {}", addr, peer);
+            // Peers which don't listen for disconnections aren't told
+            let _ = senders.disconnections.try_send(*peer);
+        }
+    }
+
+    fn sender(&self, peer: &SocketAddr) -> Result<MessageSender, Error> {
+        self.peers
+            .lock()
+            .ok()
+            .and_then(|peers| peers.get(peer).map(|senders| senders.messages.clone()))
+            .ok_or_else(|| Error::Transport(format!("No loopback peer at {}", peer)))
+    }
+
+    fn first_reachable(&self, contacts: &[SocketAddr]) -> Result<SocketAddr, Error> {
+        contacts
+            .iter()
+            .find(|contact| self.sender(contact).is_ok())
+            .copied()
+            .ok_or_else(|| Error::Transport("None of the contacts is reachable".to_string()))
+    }
+}
+
+/// A peer of a `LoopbackNetwork`.
+pub struct LoopbackTransport {
+    network: LoopbackNetwork,
+    addr: SocketAddr,
+    contacts: RwLock<Vec<SocketAddr>>,
+    streams: RwLock<Option<(IncomingMessages, Disconnections)>>,
+}
+
+impl LoopbackTransport {
+    /// Take the stream of messages sent to this peer, for peers which accept
+    /// connections rather than bootstrap to others.
+    pub async fn incoming_messages(&self) -> Option<IncomingMessages> {
+        self.streams
+            .write()
+            .await
+            .take()
+            .map(|(incoming_messages, _)| incoming_messages)
+    }
+}
+
+impl Drop for LoopbackTransport {
+    fn drop(&mut self) {
+        if let Ok(mut peers) = self.network.peers.lock() {
+            let _ = peers.remove(&self.addr);
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for LoopbackTransport {
+    async fn bootstrap(&self) -> Result<Bootstrapped, Error> {
+        let contacts = self.contacts.read().await.clone();
+        let peer = self.network.first_reachable(&contacts)?;
+        let (incoming_messages, disconnections) =
+            self.streams.write().await.take().ok_or_else(|| {
+                Error::Transport("Loopback transport has already bootstrapped".to_string())
+            })?;
+
+        Ok(Bootstrapped {
+            peer,
+            contacts,
+            incoming_messages,
+            disconnections,
+        })
+    }
+
+    async fn rebootstrap(&self, contacts: &[SocketAddr]) -> Result<SocketAddr, Error> {
+        self.network.first_reachable(contacts)
+    }
+
+    async fn update_bootstrap_contacts(&self, contacts: &[SocketAddr]) {
+        *self.contacts.write().await = contacts.to_vec();
+    }
+
+    async fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.addr)
+    }
+
+    async fn connect_to(&self, peer: &SocketAddr) -> Result<(), Error> {
+        let _ = self.network.sender(peer)?;
+        Ok(())
+    }
+
+    async fn send_message(&self, msg: Bytes, peer: &SocketAddr) -> Result<(), Error> {
+        self.network
+            .sender(peer)?
+            .send((self.addr, msg))
+            .await
+            .map_err(|_| Error::Transport(format!("Loopback peer at {} has gone", peer)))
+    }
+
+    async fn disconnect_from(&self, _peer: &SocketAddr) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+
+    #[tokio::test]
+    async fn loopback_peers_exchange_messages() -> Result<()> {
+        let network = LoopbackNetwork::new();
+        let node = network.transport(&[]);
+        let node_addr = node.local_addr().await?;
+        let mut node_messages = node
+            .incoming_messages()
+            .await
+            .ok_or_else(|| anyhow!("node messages already taken"))?;
+
+        let client = network.transport(&[node_addr]);
+        let mut bootstrapped = client.bootstrap().await?;
+        assert_eq!(bootstrapped.peer, node_addr);
+
+        client
+            .send_message(Bytes::from_static(b"ping"), &node_addr)
+            .await?;
+        let (src, msg) = node_messages
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("node stream closed"))?;
+        assert_eq!(src, client.local_addr().await?);
+        assert_eq!(msg, Bytes::from_static(b"ping"));
+
+        node.send_message(Bytes::from_static(b"pong"), &src).await?;
+        let (src, msg) = bootstrapped
+            .incoming_messages
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("client stream closed"))?;
+        assert_eq!(src, node_addr);
+        assert_eq!(msg, Bytes::from_static(b"pong"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn loopback_peers_are_told_of_disconnections() -> Result<()> {
+        let network = LoopbackNetwork::new();
+        let node = network.transport(&[]);
+        let node_addr = node.local_addr().await?;
+
+        let client = network.transport(&[node_addr]);
+        let mut bootstrapped = client.bootstrap().await?;
+
+        network.disconnect_peers_from(&node_addr);
+        let peer = bootstrapped
+            .disconnections
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("client disconnections closed"))?;
+        assert_eq!(peer, node_addr);
+        client.connect_to(&node_addr).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn loopback_bootstrap_fails_without_reachable_contacts() -> Result<()> {
+        let network = LoopbackNetwork::new();
+        let gone_addr = network.transport(&[]).local_addr().await?;
+
+        let client = network.transport(&[gone_addr]);
+        assert!(client.bootstrap().await.is_err());
+        assert!(client
+            .send_message(Bytes::from_static(b"ping"), &gone_addr)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Transports the client can use to exchange messages with the network.
+//!
+//! The client's session only relies on the [`Transport`] trait, so the wire layer can be swapped:
+//! [`QuicP2pTransport`] talks to a live network over qp2p, while [`LoopbackNetwork`] connects
+//! [`LoopbackTransport`]s to each other in memory.
+
+mod loopback;
+mod quic;
+
+pub use self::loopback::{LoopbackNetwork, LoopbackTransport};
+pub use self::quic::QuicP2pTransport;
+
+use crate::Error;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::net::SocketAddr;
+use tokio::sync::mpsc::Receiver;
+
+/// Stream of messages received from peers, along with the address of the sender.
+pub type IncomingMessages = Receiver<(SocketAddr, Bytes)>;
+
+/// Stream of addresses of the peers we got disconnected from.
+pub type Disconnections = Receiver<SocketAddr>;
+
+/// Outcome of bootstrapping a transport to the network.
+#[derive(Debug)]
+pub struct Bootstrapped {
+    /// The peer we bootstrapped to.
+    pub peer: SocketAddr,
+    /// The contacts which were known at the time of bootstrapping.
+    pub contacts: Vec<SocketAddr>,
+    /// Messages received from any peer from now on.
+    pub incoming_messages: IncomingMessages,
+    /// Peers we lose connection with from now on.
+    pub disconnections: Disconnections,
+}
+
+/// The wire layer used by the client to send and receive messages.
+///
+/// Implementations are free to decide how peers are reached, the client only deals with
+/// serialised messages and the socket addresses of the peers they are exchanged with.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Bootstrap to one of the known contacts, returning the streams of incoming messages and
+    /// disconnections. This is expected to be called once per transport.
+    async fn bootstrap(&self) -> Result<Bootstrapped, Error>;
+
+    /// Bootstrap again to one of the given contacts, returning the peer we bootstrapped to.
+    async fn rebootstrap(&self, contacts: &[SocketAddr]) -> Result<SocketAddr, Error>;
+
+    /// Replace the contacts used for any subsequent bootstrapping.
+    async fn update_bootstrap_contacts(&self, contacts: &[SocketAddr]);
+
+    /// Address the transport can be reached at.
+    async fn local_addr(&self) -> Result<SocketAddr, Error>;
+
+    /// Connect to a peer, this is a no-op if we are already connected to it.
+    async fn connect_to(&self, peer: &SocketAddr) -> Result<(), Error>;
+
+    /// Send a message to a peer.
+    async fn send_message(&self, msg: Bytes, peer: &SocketAddr) -> Result<(), Error>;
+
+    /// Disconnect from a peer.
+    async fn disconnect_from(&self, peer: &SocketAddr) -> Result<(), Error>;
+}
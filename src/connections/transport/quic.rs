@@ -0,0 +1,110 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Bootstrapped, Transport};
+use crate::Error;
+use async_trait::async_trait;
+use bytes::Bytes;
+use log::{debug, trace};
+use qp2p::{Config as QuicP2pConfig, Endpoint, QuicP2p};
+use std::net::SocketAddr;
+use tokio::sync::{mpsc::channel, RwLock};
+
+// Number of incoming messages and disconnection events buffered before applying backpressure
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Transport talking to the network over qp2p.
+pub struct QuicP2pTransport {
+    qp2p: RwLock<QuicP2p>,
+    endpoint: RwLock<Option<Endpoint>>,
+}
+
+impl QuicP2pTransport {
+    /// Create a qp2p transport with the given configuration.
+    pub fn new(qp2p_config: QuicP2pConfig) -> Result<Self, Error> {
+        debug!("QP2p config: {:?}", qp2p_config);
+
+        let qp2p = QuicP2p::with_config(Some(qp2p_config), Default::default(), true)?;
+        Ok(Self {
+            qp2p: RwLock::new(qp2p),
+            endpoint: RwLock::new(None),
+        })
+    }
+
+    async fn endpoint(&self) -> Result<Endpoint, Error> {
+        match self.endpoint.read().await.clone() {
+            Some(endpoint) => Ok(endpoint),
+            None => {
+                trace!("self.endpoint was None");
+                Err(Error::NotBootstrapped)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for QuicP2pTransport {
+    async fn bootstrap(&self) -> Result<Bootstrapped, Error> {
+        let qp2p = self.qp2p.read().await.clone();
+        let (endpoint, _, mut qp2p_messages, mut qp2p_disconnections, peer) =
+            qp2p.bootstrap().await?;
+        let contacts = endpoint.bootstrap_nodes().to_vec();
+        *self.endpoint.write().await = Some(endpoint);
+
+        let (message_sender, incoming_messages) = channel(CHANNEL_CAPACITY);
+        let _ = tokio::spawn(async move {
+            while let Some(message) = qp2p_messages.next().await {
+                if message_sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (disconnection_sender, disconnections) = channel(CHANNEL_CAPACITY);
+        let _ = tokio::spawn(async move {
+            while let Some(peer) = qp2p_disconnections.next().await {
+                if disconnection_sender.send(peer).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Bootstrapped {
+            peer,
+            contacts,
+            incoming_messages,
+            disconnections,
+        })
+    }
+
+    async fn rebootstrap(&self, contacts: &[SocketAddr]) -> Result<SocketAddr, Error> {
+        let endpoint = self.endpoint().await?;
+        let qp2p = self.qp2p.read().await.clone();
+        Ok(qp2p.rebootstrap(&endpoint, contacts).await?)
+    }
+
+    async fn update_bootstrap_contacts(&self, contacts: &[SocketAddr]) {
+        self.qp2p.write().await.update_bootstrap_contacts(contacts);
+    }
+
+    async fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.endpoint().await?.socket_addr())
+    }
+
+    async fn connect_to(&self, peer: &SocketAddr) -> Result<(), Error> {
+        Ok(self.endpoint().await?.connect_to(peer).await?)
+    }
+
+    async fn send_message(&self, msg: Bytes, peer: &SocketAddr) -> Result<(), Error> {
+        Ok(self.endpoint().await?.send_message(msg, peer).await?)
+    }
+
+    async fn disconnect_from(&self, peer: &SocketAddr) -> Result<(), Error> {
+        Ok(self.endpoint().await?.disconnect_from(peer).await?)
+    }
+}
@@ -14,7 +14,7 @@ use sn_messaging::{
     MessageId,
 };
 pub use sn_transfers::Error as TransfersError;
-use std::io;
+use std::{io, net::SocketAddr};
 
 use thiserror::Error;
 
@@ -61,6 +61,15 @@ pub enum Error {
     /// Could not query elder.
     #[error("Failed to obtain any response")]
     NoResponse,
+    /// Elders returned differing responses to a query, and not enough of them agreed on one.
+    #[error("Elders {elders:?} returned conflicting responses to query w/id {msg_id:?}")]
+    ConflictingQueryResponses {
+        /// Elders which dissented from the response most of them returned, or all those which
+        /// responded if none was returned by more of them than any other
+        elders: Vec<SocketAddr>,
+        /// Message ID that was used to send the query
+        msg_id: MessageId,
+    },
     /// No BLS section key known.
     #[error("No BLS Section Key available")]
     NoBlsSectionKey,
@@ -88,6 +97,9 @@ pub enum Error {
     /// Incorrect user permissions were returned
     #[error(" Incorrect user permissions were returned")]
     IncorrectPermissions,
+    /// The client was built read-only, so it can't write to the network.
+    #[error("Client is read-only, writes are rejected")]
+    ReadOnlyClient,
     /// Unexpcted transfer event received
     #[error("Unexpcted transfer event received {0:?}")]
     UnexpectedTransferEvent(Event),
@@ -106,6 +118,14 @@ pub enum Error {
     /// Transfer actor failed generating a transfer
     #[error("No transfer generated by transfer actor")]
     NoTransferGenerated,
+    /// The memo attached to a transfer is too long
+    #[error("Transfer memo of {len} bytes is longer than the maximum of {max} bytes")]
+    MemoTooLong {
+        /// Length of the memo
+        len: usize,
+        /// Maximum length of a memo
+        max: usize,
+    },
     /// Transfer actor did not find any events to register locally
     #[error("Transfer actor did not find any events to register locally")]
     NoTransferEventsForLocalActor,
@@ -145,6 +165,9 @@ pub enum Error {
     /// QuicP2p error.
     #[error(transparent)]
     QuicP2p(#[from] QuicP2pError),
+    /// Error raised by the transport in use
+    #[error("Transport error: {0}")]
+    Transport(String),
     /// Bincode error
     #[error(transparent)]
     Serialisation(#[from] Box<bincode::ErrorKind>),
@@ -74,7 +74,8 @@ mod errors;
 
 // Export public API.
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
+pub use connections::transport;
 pub use errors::{Error, ErrorMessage, TransfersError};
 pub use qp2p::Config as QuicP2pConfig;
 
@@ -17,6 +17,8 @@ use rand::distributions::{Alphanumeric, Distribution, Standard};
 use rand::rngs::OsRng;
 use rand::{self, Rng};
 use serde::{Deserialize, Serialize};
+use sn_data_types::{Keypair, Signature};
+use sn_messaging::{client::ClientSigned, MessageId};
 
 /// Length of the symmetric encryption key.
 pub const SYM_ENC_KEY_LEN: usize = 32;
@@ -72,6 +74,41 @@ struct SymmetricEnc {
     cipher_text: Vec<u8>,
 }
 
+/// Bytes signed by a client to authenticate a query or command: the serialised payload along with
+/// the id of the message carrying it, so that the signature can't be reused for another message.
+pub fn client_signed_bytes<T: Serialize>(
+    payload: &T,
+    msg_id: &MessageId,
+) -> Result<Vec<u8>, Error> {
+    Ok(serialize(&(payload, msg_id))?)
+}
+
+/// Sign a query or command to be sent with the given message id.
+pub fn sign_client_msg<T: Serialize>(
+    keypair: &Keypair,
+    payload: &T,
+    msg_id: &MessageId,
+) -> Result<ClientSigned, Error> {
+    let signature: Signature = keypair.sign(&client_signed_bytes(payload, msg_id)?);
+    Ok(ClientSigned {
+        public_key: keypair.public_key(),
+        signature,
+    })
+}
+
+/// Verify that a query or command was signed by the client for the given message id, as elders
+/// receiving it would.
+pub fn verify_client_signed<T: Serialize>(
+    payload: &T,
+    msg_id: &MessageId,
+    client_signed: &ClientSigned,
+) -> Result<(), Error> {
+    let bytes = client_signed_bytes(payload, msg_id)?;
+    Ok(client_signed
+        .public_key
+        .verify(&client_signed.signature, &bytes)?)
+}
+
 /// Generates a symmetric encryption key
 pub fn generate_sym_enc_key() -> SymEncKey {
     rand::random()
@@ -186,6 +223,29 @@ mod tests {
         assert_eq!(str2.chars().count(), SIZE);
     }
 
+    // Test that the client signature authenticates both the payload and the message id.
+    #[test]
+    fn client_signature_covers_payload_and_msg_id() -> Result<(), Error> {
+        use sn_data_types::PublicKey;
+        use sn_messaging::client::{Query, TransferQuery};
+
+        let keypair = Keypair::new_ed25519(&mut OsRng);
+        let query = Query::Transfer(TransferQuery::GetBalance(keypair.public_key()));
+        let msg_id = MessageId::new();
+        let client_signed = sign_client_msg(&keypair, &query, &msg_id)?;
+        verify_client_signed(&query, &msg_id, &client_signed)?;
+
+        // a tampered payload fails verification
+        let other_pk: PublicKey = Keypair::new_ed25519(&mut OsRng).public_key();
+        let tampered = Query::Transfer(TransferQuery::GetBalance(other_pk));
+        assert!(verify_client_signed(&tampered, &msg_id, &client_signed).is_err());
+
+        // and so does replaying the signature in another message
+        assert!(verify_client_signed(&query, &MessageId::new(), &client_signed).is_err());
+
+        Ok(())
+    }
+
     // Test `generate_random_vector` and that the results are not repeated.
     #[test]
     fn random_vector() {
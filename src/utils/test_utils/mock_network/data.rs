@@ -0,0 +1,330 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use log::trace;
+use sn_data_types::{
+    register::{Action as RegisterAction, Address as RegisterAddress, Permissions, Register, User},
+    Blob, BlobAddress, DataAddress, Map, MapAction, MapAddress, MapEntries, MapValue, MapValues,
+    PublicKey, Sequence, SequenceAction, SequenceAddress, SequencePermissions, SequenceUser,
+};
+use sn_messaging::client::{
+    convert_dt_error_to_error_message, BlobRead, BlobWrite, DataCmd, DataQuery,
+    Error as ErrorMessage, MapRead, MapWrite, QueryResponse, RegisterRead, RegisterWrite,
+    SequenceRead, SequenceWrite,
+};
+use std::collections::HashMap;
+
+type Result<T> = std::result::Result<T, ErrorMessage>;
+
+// All the data held by the mock section
+#[derive(Default)]
+pub(super) struct DataStore {
+    blobs: HashMap<BlobAddress, Blob>,
+    maps: HashMap<MapAddress, Map>,
+    sequences: HashMap<SequenceAddress, Sequence>,
+    registers: HashMap<RegisterAddress, Register>,
+}
+
+impl DataStore {
+    pub(super) fn read(&self, query: DataQuery, requester: PublicKey) -> QueryResponse {
+        trace!("Mock section handling data query: {:?}", query);
+        match query {
+            DataQuery::Blob(BlobRead::Get(address)) => {
+                QueryResponse::GetBlob(self.get_blob(address, requester))
+            }
+            DataQuery::Map(read) => self.read_map(read, requester),
+            DataQuery::Sequence(SequenceRead::Get(address)) => {
+                QueryResponse::GetSequence(self.get_sequence(address, requester).cloned())
+            }
+            DataQuery::Register(RegisterRead::Get(address)) => {
+                QueryResponse::GetRegister(self.get_register(address, requester).cloned())
+            }
+        }
+    }
+
+    pub(super) fn write(&mut self, cmd: DataCmd, requester: PublicKey) -> Result<()> {
+        trace!("Mock section handling data cmd: {:?}", cmd);
+        match cmd {
+            DataCmd::Blob(write) => self.write_blob(write, requester),
+            DataCmd::Map(write) => self.write_map(write, requester),
+            DataCmd::Sequence(write) => self.write_sequence(write, requester),
+            DataCmd::Register(write) => self.write_register(write, requester),
+        }
+    }
+
+    // ---------- Blob ----------
+
+    fn get_blob(&self, address: BlobAddress, requester: PublicKey) -> Result<Blob> {
+        let blob = self
+            .blobs
+            .get(&address)
+            .ok_or(ErrorMessage::DataNotFound(DataAddress::Blob(address)))?;
+        if let Blob::Private(data) = blob {
+            if *data.owner() != requester {
+                return Err(ErrorMessage::AccessDenied(requester));
+            }
+        }
+
+        Ok(blob.clone())
+    }
+
+    fn write_blob(&mut self, write: BlobWrite, requester: PublicKey) -> Result<()> {
+        match write {
+            BlobWrite::New(blob) => {
+                if let Blob::Private(data) = &blob {
+                    if *data.owner() != requester {
+                        return Err(ErrorMessage::AccessDenied(requester));
+                    }
+                }
+                // Storing the same Blob again is not a conflict
+                let _ = self.blobs.insert(*blob.address(), blob);
+                Ok(())
+            }
+            BlobWrite::DeletePrivate(address) => {
+                if address.is_public() {
+                    return Err(ErrorMessage::InvalidOperation(
+                        "Public Blobs cannot be deleted".to_string(),
+                    ));
+                }
+                let _ = self.get_blob(address, requester)?;
+                let _ = self.blobs.remove(&address);
+                Ok(())
+            }
+        }
+    }
+
+    // ---------- Map ----------
+
+    fn get_map(&self, address: &MapAddress, requester: PublicKey) -> Result<&Map> {
+        let map = self
+            .maps
+            .get(address)
+            .ok_or(ErrorMessage::DataNotFound(DataAddress::Map(*address)))?;
+        map.check_permissions(MapAction::Read, &requester)
+            .map_err(convert_dt_error_to_error_message)?;
+
+        Ok(map)
+    }
+
+    fn get_map_mut(&mut self, address: &MapAddress) -> Result<&mut Map> {
+        self.maps
+            .get_mut(address)
+            .ok_or(ErrorMessage::DataNotFound(DataAddress::Map(*address)))
+    }
+
+    fn read_map(&self, read: MapRead, requester: PublicKey) -> QueryResponse {
+        match read {
+            MapRead::Get(address) => {
+                QueryResponse::GetMap(self.get_map(&address, requester).map(Map::clone))
+            }
+            MapRead::GetShell(address) => {
+                QueryResponse::GetMapShell(self.get_map(&address, requester).map(Map::shell))
+            }
+            MapRead::GetVersion(address) => {
+                QueryResponse::GetMapVersion(self.get_map(&address, requester).map(Map::version))
+            }
+            MapRead::GetValue { address, key } => {
+                QueryResponse::GetMapValue(self.get_map(&address, requester).and_then(|map| {
+                    match map {
+                        Map::Seq(data) => data.get(&key).cloned().map(MapValue::Seq),
+                        Map::Unseq(data) => data.get(&key).cloned().map(MapValue::Unseq),
+                    }
+                    .ok_or(ErrorMessage::NoSuchEntry)
+                }))
+            }
+            MapRead::ListEntries(address) => QueryResponse::ListMapEntries(
+                self.get_map(&address, requester).map(|map| match map {
+                    Map::Seq(data) => MapEntries::Seq(data.entries().clone()),
+                    Map::Unseq(data) => MapEntries::Unseq(data.entries().clone()),
+                }),
+            ),
+            MapRead::ListKeys(address) => {
+                QueryResponse::ListMapKeys(self.get_map(&address, requester).map(Map::keys))
+            }
+            MapRead::ListValues(address) => QueryResponse::ListMapValues(
+                self.get_map(&address, requester).map(|map| match map {
+                    Map::Seq(data) => MapValues::Seq(data.values()),
+                    Map::Unseq(data) => MapValues::Unseq(data.values()),
+                }),
+            ),
+            MapRead::ListPermissions(address) => QueryResponse::ListMapPermissions(
+                self.get_map(&address, requester).map(Map::permissions),
+            ),
+            MapRead::ListUserPermissions { address, user } => {
+                QueryResponse::ListMapUserPermissions(self.get_map(&address, requester).and_then(
+                    |map| {
+                        map.user_permissions(&user)
+                            .map(Clone::clone)
+                            .map_err(convert_dt_error_to_error_message)
+                    },
+                ))
+            }
+        }
+    }
+
+    fn write_map(&mut self, write: MapWrite, requester: PublicKey) -> Result<()> {
+        match write {
+            MapWrite::New(map) => {
+                if map.owner() != requester {
+                    return Err(ErrorMessage::AccessDenied(requester));
+                }
+                if self.maps.contains_key(map.address()) {
+                    return Err(ErrorMessage::DataExists);
+                }
+                let _ = self.maps.insert(*map.address(), map);
+                Ok(())
+            }
+            MapWrite::Delete(address) => {
+                let map = self.get_map_mut(&address)?;
+                if map.owner() != requester {
+                    return Err(ErrorMessage::AccessDenied(requester));
+                }
+                let _ = self.maps.remove(&address);
+                Ok(())
+            }
+            MapWrite::SetUserPermissions {
+                address,
+                user,
+                permissions,
+                version,
+            } => {
+                let map = self.get_map_mut(&address)?;
+                map.check_permissions(MapAction::ManagePermissions, &requester)
+                    .and_then(|()| map.set_user_permissions(user, permissions, version))
+                    .map_err(convert_dt_error_to_error_message)
+            }
+            MapWrite::DelUserPermissions {
+                address,
+                user,
+                version,
+            } => {
+                let map = self.get_map_mut(&address)?;
+                map.check_permissions(MapAction::ManagePermissions, &requester)
+                    .and_then(|()| map.del_user_permissions(user, version))
+                    .map_err(convert_dt_error_to_error_message)
+            }
+            MapWrite::Edit { address, changes } => {
+                let map = self.get_map_mut(&address)?;
+                map.mutate_entries(changes, &requester)
+                    .map_err(convert_dt_error_to_error_message)
+            }
+        }
+    }
+
+    // ---------- Sequence ----------
+
+    fn get_sequence(&self, address: SequenceAddress, requester: PublicKey) -> Result<&Sequence> {
+        let sequence = self
+            .sequences
+            .get(&address)
+            .ok_or(ErrorMessage::DataNotFound(DataAddress::Sequence(address)))?;
+
+        if sequence.is_private() {
+            match sequence.permissions(SequenceUser::Key(requester), None) {
+                Ok(SequencePermissions::Private(perms))
+                    if perms.is_allowed(SequenceAction::Read) => {}
+                _ => return Err(ErrorMessage::AccessDenied(requester)),
+            }
+        }
+
+        Ok(sequence)
+    }
+
+    fn write_sequence(&mut self, write: SequenceWrite, requester: PublicKey) -> Result<()> {
+        match write {
+            SequenceWrite::New(sequence) => {
+                if self.sequences.contains_key(sequence.address()) {
+                    return Err(ErrorMessage::DataExists);
+                }
+                let _ = self.sequences.insert(*sequence.address(), sequence);
+                Ok(())
+            }
+            SequenceWrite::Edit(op) => {
+                let sequence =
+                    self.sequences
+                        .get_mut(&op.address)
+                        .ok_or(ErrorMessage::DataNotFound(DataAddress::Sequence(
+                            op.address,
+                        )))?;
+                sequence
+                    .apply_op(op)
+                    .map_err(convert_dt_error_to_error_message)
+            }
+            SequenceWrite::Delete(address) => {
+                if address.is_public() {
+                    return Err(ErrorMessage::InvalidOperation(
+                        "Public Sequences cannot be deleted".to_string(),
+                    ));
+                }
+                let owner = self
+                    .get_sequence(address, requester)?
+                    .private_policy(None)
+                    .map_err(convert_dt_error_to_error_message)?
+                    .owner;
+                if owner != requester {
+                    return Err(ErrorMessage::AccessDenied(requester));
+                }
+                let _ = self.sequences.remove(&address);
+                Ok(())
+            }
+        }
+    }
+
+    // ---------- Register ----------
+
+    fn get_register(&self, address: RegisterAddress, requester: PublicKey) -> Result<&Register> {
+        let register = self
+            .registers
+            .get(&address)
+            .ok_or(ErrorMessage::DataNotFound(DataAddress::Register(address)))?;
+
+        if register.is_private() && register.owner() != requester {
+            match register.permissions(User::Key(requester), None) {
+                Ok(Permissions::Private(perms)) if perms.is_allowed(RegisterAction::Read) => {}
+                _ => return Err(ErrorMessage::AccessDenied(requester)),
+            }
+        }
+
+        Ok(register)
+    }
+
+    fn write_register(&mut self, write: RegisterWrite, requester: PublicKey) -> Result<()> {
+        match write {
+            RegisterWrite::New(register) => {
+                if self.registers.contains_key(register.address()) {
+                    return Err(ErrorMessage::DataExists);
+                }
+                let _ = self.registers.insert(*register.address(), register);
+                Ok(())
+            }
+            RegisterWrite::Edit(op) => {
+                let register =
+                    self.registers
+                        .get_mut(&op.address)
+                        .ok_or(ErrorMessage::DataNotFound(DataAddress::Register(
+                            op.address,
+                        )))?;
+                register
+                    .apply_op(op)
+                    .map_err(convert_dt_error_to_error_message)
+            }
+            RegisterWrite::Delete(address) => {
+                if address.is_public() {
+                    return Err(ErrorMessage::InvalidOperation(
+                        "Public Registers cannot be deleted".to_string(),
+                    ));
+                }
+                if self.get_register(address, requester)?.owner() != requester {
+                    return Err(ErrorMessage::AccessDenied(requester));
+                }
+                let _ = self.registers.remove(&address);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,485 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+// Data storage of the mock section
+mod data;
+// Wallets and transfer validation of the mock section
+mod transfers;
+
+use self::{data::DataStore, transfers::Wallets};
+use crate::{
+    transport::{IncomingMessages, LoopbackNetwork, LoopbackTransport, Transport},
+    utils::verify_client_signed,
+    Error,
+};
+use bytes::Bytes;
+use log::{debug, error, trace, warn};
+use serde::Serialize;
+use sn_data_types::PublicKey;
+use sn_messaging::{
+    client::{
+        ClientMsg, ClientSigned, Cmd, CmdError, Event, ProcessMsg, Query, QueryResponse,
+        TransferCmd, TransferError, TransferQuery,
+    },
+    section_info::{Error as SectionInfoError, ErrorResponse, GetSectionResponse, SectionInfoMsg},
+    MessageId, MessageType, SectionAuthorityProvider, WireMsg,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+};
+use threshold_crypto::{PublicKeySet, SecretKeySet, SecretKeyShare};
+use tokio::sync::RwLock;
+use xor_name::{Prefix, XorName};
+
+// Number of elders the mock section is made of
+const MOCK_ELDER_COUNT: usize = 5;
+
+// Number of already processed command ids we keep track of, so that the
+// same command sent to every elder is only applied once
+const PROCESSED_CMDS_CAPACITY: usize = 10_000;
+
+thread_local! {
+    // Each test runs in its own thread and tokio runtime, so the mock section
+    // is shared by all the clients created within the same test.
+    static SHARED_NETWORK: RefCell<Option<MockNetwork>> = RefCell::new(None);
+}
+
+/// An in-process stand-in for a network section.
+///
+/// It runs a set of elders on a `LoopbackNetwork` which answer the `SectionInfoMsg` handshake,
+/// `Query`s and `Cmd`s for Blob, Map, Sequence, Register and Transfer, and issue
+/// `TransferValidated` signature shares, so a `Client` can be bootstrapped to it as it would
+/// to a live section.
+#[derive(Clone)]
+pub struct MockNetwork {
+    network: LoopbackNetwork,
+    section: Arc<MockSection>,
+}
+
+struct MockSection {
+    secret_key_set: SecretKeySet,
+    // Key set the section is known by, which changes as the section churns. The elders keep
+    // their key shares though, so transfers are validated with `secret_key_set` throughout.
+    section_key_set: RwLock<PublicKeySet>,
+    elders: BTreeMap<XorName, SocketAddr>,
+    data: RwLock<DataStore>,
+    wallets: RwLock<Wallets>,
+    processed_cmds: RwLock<ProcessedCmds>,
+    // Ids of the messages rejected for being sent with outdated section info
+    interrupted_msgs: RwLock<HashSet<MessageId>>,
+}
+
+// The elder a message was received by
+struct Elder {
+    index: usize,
+    key_share: SecretKeyShare,
+}
+
+#[derive(Default)]
+struct ProcessedCmds {
+    ids: HashSet<MessageId>,
+    order: VecDeque<MessageId>,
+}
+
+impl ProcessedCmds {
+    // Returns true if the id had not been seen before
+    fn insert(&mut self, id: MessageId) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > PROCESSED_CMDS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                let _ = self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+impl MockNetwork {
+    /// Start a new mock section on the current tokio runtime.
+    pub async fn start() -> Result<Self, Error> {
+        let mut rng = rand::thread_rng();
+        let secret_key_set = SecretKeySet::random(MOCK_ELDER_COUNT / 2, &mut rng);
+
+        let network = LoopbackNetwork::new();
+        let mut elders = BTreeMap::new();
+        let mut transports = Vec::new();
+        for _ in 0..MOCK_ELDER_COUNT {
+            let transport = network.transport(&[]);
+            let _ = elders.insert(XorName::random(), transport.local_addr().await?);
+            transports.push(transport);
+        }
+
+        let section = Arc::new(MockSection {
+            section_key_set: RwLock::new(secret_key_set.public_keys()),
+            secret_key_set,
+            elders,
+            data: RwLock::new(DataStore::default()),
+            wallets: RwLock::new(Wallets::default()),
+            processed_cmds: RwLock::new(ProcessedCmds::default()),
+            interrupted_msgs: RwLock::new(HashSet::new()),
+        });
+
+        for (index, transport) in transports.into_iter().enumerate() {
+            let incoming_messages = transport
+                .incoming_messages()
+                .await
+                .ok_or(Error::NoElderListenerEstablished)?;
+            let elder = Elder {
+                index,
+                key_share: section.secret_key_set.secret_key_share(index),
+            };
+            debug!(
+                "Mock elder #{} listening on {}",
+                index,
+                transport.local_addr().await?
+            );
+            spawn_elder(section.clone(), elder, transport, incoming_messages);
+        }
+
+        Ok(Self { network, section })
+    }
+
+    /// Return the mock section of the current thread, starting it if needed.
+    pub async fn shared() -> Result<Self, Error> {
+        if let Some(network) = SHARED_NETWORK.with(|shared| shared.borrow().clone()) {
+            return Ok(network);
+        }
+
+        let network = Self::start().await?;
+        SHARED_NETWORK.with(|shared| *shared.borrow_mut() = Some(network.clone()));
+
+        Ok(network)
+    }
+
+    /// Addresses of the mock elders, to be used as bootstrap contacts.
+    pub fn contacts(&self) -> HashSet<SocketAddr> {
+        self.section.elders.values().cloned().collect()
+    }
+
+    /// A new transport, bootstrapping to the mock elders, for a client to connect with.
+    pub fn transport(&self) -> Arc<dyn Transport> {
+        let contacts = self.section.elders.values().cloned().collect::<Vec<_>>();
+        Arc::new(self.network.transport(&contacts))
+    }
+
+    /// The BLS public key set of the mock section.
+    pub fn public_key_set(&self) -> PublicKeySet {
+        self.section.secret_key_set.public_keys()
+    }
+
+    /// Change the key the section is known by, as a churn would. The messages then sent with
+    /// the previous section info are rejected with a `SectionInfoUpdate`, so clients update
+    /// their section info and send them again.
+    pub async fn churn(&self) {
+        let secret_key_set = SecretKeySet::random(MOCK_ELDER_COUNT / 2, &mut rand::thread_rng());
+        *self.section.section_key_set.write().await = secret_key_set.public_keys();
+        debug!("Mock section churned");
+    }
+
+    /// Drop the connections of every client to the mock elders, as elders restarting would.
+    /// The clients are told as their transport would tell them, and can reconnect right away.
+    pub fn drop_connections(&self) {
+        for elder in self.section.elders.values() {
+            self.network.disconnect_peers_from(elder);
+        }
+        debug!("Mock elders dropped their connections");
+    }
+
+    /// Ids of the messages rejected for having been sent with outdated section info.
+    pub async fn interrupted_msgs(&self) -> HashSet<MessageId> {
+        self.section.interrupted_msgs.read().await.clone()
+    }
+}
+
+fn spawn_elder(
+    section: Arc<MockSection>,
+    elder: Elder,
+    transport: LoopbackTransport,
+    mut incoming_messages: IncomingMessages,
+) {
+    let _ = tokio::spawn(async move {
+        while let Some((src, bytes)) = incoming_messages.recv().await {
+            let replies = match section.handle_message(&elder, bytes).await {
+                Ok(replies) => replies,
+                Err(err) => {
+                    error!(
+                        "Mock elder #{} failed to handle message: {:?}",
+                        elder.index, err
+                    );
+                    continue;
+                }
+            };
+            for reply in replies {
+                if let Err(err) = transport.send_message(reply, &src).await {
+                    warn!(
+                        "Mock elder #{} failed to reply to {}: {:?}",
+                        elder.index, src, err
+                    );
+                }
+            }
+        }
+        trace!("Mock elder #{} stopped listening", elder.index);
+    });
+}
+
+// Elders drop the messages whose signature doesn't match their content
+fn is_signed_by_client<T: Serialize>(
+    elder: &Elder,
+    payload: &T,
+    msg_id: &MessageId,
+    client_signed: &ClientSigned,
+) -> bool {
+    match verify_client_signed(payload, msg_id, client_signed) {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(
+                "Mock elder #{} dropping message {:?} with an invalid signature: {:?}",
+                elder.index, msg_id, err
+            );
+            false
+        }
+    }
+}
+
+impl MockSection {
+    fn section_key(&self) -> PublicKey {
+        PublicKey::Bls(self.secret_key_set.public_keys().public_key())
+    }
+
+    async fn authority_provider(&self) -> SectionAuthorityProvider {
+        SectionAuthorityProvider {
+            prefix: Prefix::default(),
+            public_key_set: self.section_key_set.read().await.clone(),
+            elders: self.elders.clone(),
+        }
+    }
+
+    // Handle a message received by one of the elders, returning the replies to send back
+    async fn handle_message(&self, elder: &Elder, bytes: Bytes) -> Result<Vec<Bytes>, Error> {
+        let section_pk = self.section_key_set.read().await.public_key();
+        match WireMsg::deserialize(bytes)? {
+            MessageType::SectionInfo {
+                msg: SectionInfoMsg::GetSectionQuery(client_pk),
+                ..
+            } => {
+                trace!("Mock elder #{} received GetSectionQuery", elder.index);
+                let response = SectionInfoMsg::GetSectionResponse(GetSectionResponse::Success(
+                    self.authority_provider().await,
+                ));
+                Ok(vec![
+                    response.serialize(XorName::from(client_pk), section_pk)?
+                ])
+            }
+            MessageType::Client {
+                msg: ClientMsg::Process(msg),
+                dest_info,
+            } => {
+                if dest_info.dest_section_pk != section_pk {
+                    if let ProcessMsg::Query {
+                        id, client_signed, ..
+                    }
+                    | ProcessMsg::Cmd {
+                        id, client_signed, ..
+                    } = &msg
+                    {
+                        return self
+                            .reject_outdated(elder, *id, client_signed.public_key)
+                            .await;
+                    }
+                }
+
+                let (requester, replies) = match msg {
+                    ProcessMsg::Query {
+                        id,
+                        query,
+                        client_signed,
+                    } => {
+                        if !is_signed_by_client(elder, &query, &id, &client_signed) {
+                            return Ok(vec![]);
+                        }
+                        let response = self.handle_query(query, &client_signed).await;
+                        let reply = ProcessMsg::QueryResponse {
+                            id: MessageId::new(),
+                            response,
+                            correlation_id: id,
+                        };
+                        (client_signed.public_key, vec![reply])
+                    }
+                    ProcessMsg::Cmd {
+                        id,
+                        cmd,
+                        client_signed,
+                    } => {
+                        if !is_signed_by_client(elder, &cmd, &id, &client_signed) {
+                            return Ok(vec![]);
+                        }
+                        let replies = self.handle_cmd(elder, id, cmd, &client_signed).await;
+                        (client_signed.public_key, replies)
+                    }
+                    msg => {
+                        warn!("Mock elder #{} ignoring message: {:?}", elder.index, msg);
+                        return Ok(vec![]);
+                    }
+                };
+
+                let dest = XorName::from(requester);
+                replies
+                    .into_iter()
+                    .map(|reply| {
+                        ClientMsg::Process(reply)
+                            .serialize(dest, section_pk)
+                            .map_err(Error::from)
+                    })
+                    .collect()
+            }
+            msg_type => {
+                warn!(
+                    "Mock elder #{} ignoring message: {:?}",
+                    elder.index, msg_type
+                );
+                Ok(vec![])
+            }
+        }
+    }
+
+    // Let the client know the message was sent with outdated section info, sending it the
+    // current one so it can send the message again
+    async fn reject_outdated(
+        &self,
+        elder: &Elder,
+        id: MessageId,
+        requester: PublicKey,
+    ) -> Result<Vec<Bytes>, Error> {
+        debug!(
+            "Mock elder #{} rejecting message {:?} sent with outdated section info",
+            elder.index, id
+        );
+        let _ = self.interrupted_msgs.write().await.insert(id);
+
+        let sap = self.authority_provider().await;
+        let section_pk = sap.public_key_set.public_key();
+        let update = SectionInfoMsg::SectionInfoUpdate(ErrorResponse {
+            correlation_id: id,
+            error: SectionInfoError::TargetSectionInfoOutdated(sap),
+        });
+
+        Ok(vec![update.serialize(XorName::from(requester), section_pk)?])
+    }
+
+    async fn handle_query(&self, query: Query, client_signed: &ClientSigned) -> QueryResponse {
+        let requester = client_signed.public_key;
+        match query {
+            Query::Data(query) => self.data.read().await.read(query, requester),
+            Query::Transfer(TransferQuery::GetBalance(pk)) => {
+                QueryResponse::GetBalance(Ok(self.wallets.read().await.balance(&pk)))
+            }
+            Query::Transfer(TransferQuery::GetHistory { at, since_version }) => {
+                QueryResponse::GetHistory(Ok(self.wallets.read().await.history(&at, since_version)))
+            }
+            Query::Transfer(TransferQuery::GetStoreCost { bytes, .. }) => {
+                QueryResponse::GetStoreCost(Ok((
+                    bytes,
+                    transfers::store_cost(bytes),
+                    self.section_key(),
+                )))
+            }
+            Query::Transfer(TransferQuery::GetReplicaKeys(_)) => {
+                QueryResponse::GetReplicaKeys(Ok(self.secret_key_set.public_keys()))
+            }
+        }
+    }
+
+    async fn handle_cmd(
+        &self,
+        elder: &Elder,
+        id: MessageId,
+        cmd: Cmd,
+        client_signed: &ClientSigned,
+    ) -> Vec<ProcessMsg> {
+        let requester = client_signed.public_key;
+
+        if let Cmd::Transfer(TransferCmd::ValidateTransfer(signed_transfer)) = &cmd {
+            // Every elder validates and sends back its own signature share
+            let validation = self.wallets.read().await.validate(
+                signed_transfer,
+                elder.index,
+                &elder.key_share,
+                self.secret_key_set.public_keys(),
+            );
+            return match validation {
+                Ok(event) => vec![ProcessMsg::Event {
+                    id: MessageId::new(),
+                    event: Event::TransferValidated { event },
+                    correlation_id: id,
+                }],
+                Err(error) => vec![cmd_error(
+                    CmdError::Transfer(TransferError::TransferValidation(error)),
+                    id,
+                )],
+            };
+        }
+
+        // The rest of commands are sent to all elders, but must only be applied once
+        if !self.processed_cmds.write().await.insert(id) {
+            return vec![];
+        }
+
+        match cmd {
+            Cmd::Transfer(TransferCmd::ValidateTransfer(_)) => vec![],
+            Cmd::Transfer(TransferCmd::RegisterTransfer(proof)) => {
+                match self.wallets.write().await.register(proof) {
+                    Ok(()) => vec![],
+                    Err(error) => vec![cmd_error(
+                        CmdError::Transfer(TransferError::TransferRegistration(error)),
+                        id,
+                    )],
+                }
+            }
+            Cmd::Transfer(TransferCmd::SimulatePayout(transfer)) => {
+                self.wallets
+                    .write()
+                    .await
+                    .simulate_payout(transfer, &self.secret_key_set);
+                vec![]
+            }
+            Cmd::Data { cmd, payment } => {
+                let result =
+                    self.wallets
+                        .write()
+                        .await
+                        .pay_for_write(&cmd, payment, self.section_key());
+                let result = match result {
+                    Ok(()) => self.data.write().await.write(cmd, requester),
+                    Err(error) => Err(error),
+                };
+                match result {
+                    Ok(()) => vec![],
+                    Err(error) => vec![cmd_error(CmdError::Data(error), id)],
+                }
+            }
+        }
+    }
+}
+
+fn cmd_error(error: CmdError, correlation_id: MessageId) -> ProcessMsg {
+    debug!(
+        "Mock section rejected cmd {:?}: {:?}",
+        correlation_id, error
+    );
+    ProcessMsg::CmdError {
+        id: MessageId::new(),
+        error,
+        correlation_id,
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bincode::serialize;
+use sn_data_types::{
+    ActorHistory, Credit, CreditAgreementProof, PublicKey, Signature, SignatureShare, SignedCredit,
+    SignedTransfer, Token, Transfer, TransferAgreementProof, TransferValidated,
+};
+use sn_messaging::client::{DataCmd, Error as ErrorMessage};
+use std::collections::HashMap;
+use threshold_crypto::{PublicKeySet, SecretKeySet, SecretKeyShare};
+
+type Result<T> = std::result::Result<T, ErrorMessage>;
+
+/// Store cost charged by the mock section, one nano per byte.
+pub(super) fn store_cost(bytes: u64) -> Token {
+    Token::from_nano(bytes.max(1))
+}
+
+// The credits and debits of every wallet known to the mock section
+#[derive(Default)]
+pub(super) struct Wallets {
+    histories: HashMap<PublicKey, ActorHistory>,
+}
+
+impl Wallets {
+    // The history of a wallet, as indexed by its debits: those before the given version are
+    // left out, while the credits are all returned
+    pub(super) fn history(&self, pk: &PublicKey, since_version: usize) -> ActorHistory {
+        match self.histories.get(pk) {
+            Some(history) => ActorHistory {
+                credits: history.credits.clone(),
+                debits: history.debits.iter().skip(since_version).cloned().collect(),
+            },
+            None => ActorHistory {
+                credits: vec![],
+                debits: vec![],
+            },
+        }
+    }
+
+    pub(super) fn balance(&self, pk: &PublicKey) -> Token {
+        let history = match self.histories.get(pk) {
+            Some(history) => history,
+            None => return Token::from_nano(0),
+        };
+        let credits: u64 = history
+            .credits
+            .iter()
+            .map(|proof| proof.signed_credit.credit.amount.as_nano())
+            .sum();
+        let debits: u64 = history
+            .debits
+            .iter()
+            .map(|proof| proof.signed_debit.debit.amount.as_nano())
+            .sum();
+
+        Token::from_nano(credits.saturating_sub(debits))
+    }
+
+    // Validate a transfer, returning this elder's signature shares over it
+    pub(super) fn validate(
+        &self,
+        signed_transfer: &SignedTransfer,
+        index: usize,
+        key_share: &SecretKeyShare,
+        replicas: PublicKeySet,
+    ) -> Result<TransferValidated> {
+        let debit = &signed_transfer.debit;
+        let sender = debit.sender();
+        if debit.amount().as_nano() > self.balance(&sender).as_nano() {
+            return Err(ErrorMessage::InsufficientBalance);
+        }
+        let expected_version = self
+            .histories
+            .get(&sender)
+            .map(|history| history.debits.len() as u64)
+            .unwrap_or(0);
+        if debit.id().counter != expected_version {
+            return Err(ErrorMessage::InvalidOperation(format!(
+                "Debit out of order, expected version {} but got {}",
+                expected_version,
+                debit.id().counter
+            )));
+        }
+
+        let debit_bytes = serialize(&signed_transfer.debit).map_err(serialisation_error)?;
+        let credit_bytes = serialize(&signed_transfer.credit).map_err(serialisation_error)?;
+
+        Ok(TransferValidated {
+            signed_debit: signed_transfer.debit.clone(),
+            signed_credit: signed_transfer.credit.clone(),
+            replica_debit_sig: SignatureShare {
+                index,
+                share: key_share.sign(&debit_bytes),
+            },
+            replica_credit_sig: SignatureShare {
+                index,
+                share: key_share.sign(&credit_bytes),
+            },
+            replicas,
+        })
+    }
+
+    // Register a transfer agreed by the section, debiting the sender and crediting the recipient
+    pub(super) fn register(&mut self, proof: TransferAgreementProof) -> Result<()> {
+        let sender = proof.signed_debit.sender();
+        let already_registered = self
+            .histories
+            .get(&sender)
+            .map(|history| {
+                history
+                    .debits
+                    .iter()
+                    .any(|debit| debit.signed_debit.id() == proof.signed_debit.id())
+            })
+            .unwrap_or(false);
+        if already_registered {
+            return Err(ErrorMessage::InvalidOperation(format!(
+                "Transfer {:?} already registered",
+                proof.signed_debit.id()
+            )));
+        }
+
+        let credit = CreditAgreementProof {
+            signed_credit: proof.signed_credit.clone(),
+            debiting_replicas_sig: proof.credit_sig.clone(),
+            debiting_replicas_keys: proof.debiting_replicas_keys.clone(),
+        };
+        self.history_mut(credit.recipient()).credits.push(credit);
+        self.history_mut(sender).debits.push(proof);
+
+        Ok(())
+    }
+
+    // Check the payment for a data write goes to the section and covers its cost,
+    // and register it if so
+    pub(super) fn pay_for_write(
+        &mut self,
+        cmd: &DataCmd,
+        payment: TransferAgreementProof,
+        section_key: PublicKey,
+    ) -> Result<()> {
+        let bytes = serialize(cmd).map_err(serialisation_error)?.len() as u64;
+        if payment.recipient() != section_key {
+            return Err(ErrorMessage::InvalidOperation(
+                "Payment was not made to the section".to_string(),
+            ));
+        }
+        if payment.amount().as_nano() < store_cost(bytes).as_nano() {
+            return Err(ErrorMessage::InvalidOperation(format!(
+                "Payment of {} does not cover the cost of storing {} bytes",
+                payment.amount(),
+                bytes
+            )));
+        }
+
+        self.register(payment)
+    }
+
+    // Credit a wallet without any sender, signing the credit with the section key
+    pub(super) fn simulate_payout(&mut self, transfer: Transfer, secret_key_set: &SecretKeySet) {
+        let secret_key = secret_key_set.secret_key();
+        let credit = Credit {
+            id: transfer.debit_id,
+            amount: transfer.amount,
+            recipient: transfer.to,
+            msg: transfer.msg,
+        };
+        let actor_signature = match serialize(&credit) {
+            Ok(bytes) => Signature::Bls(secret_key.sign(&bytes)),
+            Err(_) => return,
+        };
+        let signed_credit = SignedCredit {
+            credit,
+            actor_signature,
+        };
+        let debiting_replicas_sig = match serialize(&signed_credit) {
+            Ok(bytes) => Signature::Bls(secret_key.sign(&bytes)),
+            Err(_) => return,
+        };
+
+        self.history_mut(transfer.to)
+            .credits
+            .push(CreditAgreementProof {
+                signed_credit,
+                debiting_replicas_sig,
+                debiting_replicas_keys: secret_key_set.public_keys(),
+            });
+    }
+
+    fn history_mut(&mut self, pk: PublicKey) -> &mut ActorHistory {
+        self.histories.entry(pk).or_insert_with(|| ActorHistory {
+            credits: vec![],
+            debits: vec![],
+        })
+    }
+}
+
+fn serialisation_error(error: Box<bincode::ErrorKind>) -> ErrorMessage {
+    ErrorMessage::InvalidOperation(format!("Failed to serialise: {}", error))
+}
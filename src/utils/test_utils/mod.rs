@@ -6,6 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "mock-network")]
+mod mock_network;
 #[cfg(test)]
 mod test_client;
 #[cfg(feature = "simulated-payouts")]
@@ -13,10 +15,14 @@ mod tokens;
 
 use anyhow::{anyhow, Context, Result};
 use dirs_next::home_dir;
+#[cfg(feature = "mock-network")]
+pub use mock_network::MockNetwork;
 use std::path::Path;
 use std::{collections::HashSet, fs::File, io::BufReader, net::SocketAddr};
 #[cfg(test)]
-pub use test_client::{create_test_client, create_test_client_with, init_logger};
+pub use test_client::{
+    create_test_client, create_test_client_with, init_logger, test_client_builder,
+};
 #[cfg(feature = "simulated-payouts")]
 pub use tokens::{calculate_new_balance, gen_ed_keypair};
 
@@ -6,8 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(not(feature = "mock-network"))]
 use super::read_network_conn_info;
-use crate::{client::Client, retry_loop_for_pattern};
+#[cfg(feature = "mock-network")]
+use super::MockNetwork;
+use crate::{
+    client::{Client, ClientBuilder},
+    retry_loop_for_pattern,
+};
 use anyhow::Result;
 use sn_data_types::{Keypair, Token};
 use std::str::FromStr;
@@ -29,6 +35,18 @@ pub fn init_logger() {
     });
 }
 
+/// Start building a test client, bootstrapping to the mock section when built with the
+/// `mock-network` feature, or to the network whose connection info is on disk otherwise
+pub async fn test_client_builder() -> Result<ClientBuilder> {
+    init_logger();
+    #[cfg(feature = "mock-network")]
+    let builder = ClientBuilder::new().transport(MockNetwork::shared().await?.transport());
+    #[cfg(not(feature = "mock-network"))]
+    let builder = ClientBuilder::new().bootstrap_contacts(read_network_conn_info()?);
+
+    Ok(builder)
+}
+
 /// Create a test client without providing any specific keypair or bootstrap_config
 pub async fn create_test_client() -> Result<Client> {
     create_test_client_with(None).await
@@ -36,10 +54,15 @@ pub async fn create_test_client() -> Result<Client> {
 
 /// Create a test client optionally providing keypair and/or bootstrap_config
 /// If no keypair is provided, a check is run that a balance has been generated for the client
+///
+/// When built with the `mock-network` feature, the client is bootstrapped to an in-process
+/// mock section (shared by all clients created within the same test) instead of a live network.
 pub async fn create_test_client_with(optional_keypair: Option<Keypair>) -> Result<Client> {
-    init_logger();
-    let contact_info = read_network_conn_info()?;
-    let client = Client::new(optional_keypair.clone(), None, Some(contact_info)).await?;
+    let mut builder = test_client_builder().await?;
+    if let Some(keypair) = optional_keypair.clone() {
+        builder = builder.keypair(keypair);
+    }
+    let client = builder.build().await?;
 
     if optional_keypair.is_none() {
         // check we have some balance, 10 test coins